@@ -1,56 +1,275 @@
+pub mod arguments;
+pub mod backend;
+pub mod commandbar;
+pub mod diff;
+pub mod documentstatus;
 pub mod editorcommand;
+pub mod emacs;
+pub mod filetype;
+pub mod keymap;
+pub mod macros;
+pub mod screen;
+#[cfg(test)]
+mod snapshot;
+pub mod statusbar;
+pub mod swap;
 pub mod terminal;
 pub mod view;
-use crate::editor::editorcommand::EditorCommand;
-use crate::editor::terminal::Terminal;
+pub mod vim;
+use crate::editor::arguments::{KeyBindings, Options, Source};
+use crate::editor::backend::Backend;
+use crate::editor::commandbar::{Answer, CommandBar};
+use crate::editor::editorcommand::{EditorCommand, GoToTarget};
+use crate::editor::emacs::Emacs;
+use crate::editor::macros::Macros;
+use crate::editor::screen::Screen;
+use crate::editor::statusbar::StatusBar;
+use crate::editor::terminal::{CursorShape, Position, Size, Terminal};
 use crate::editor::view::View;
-use crossterm::event::{read, Event, KeyEvent, KeyEventKind};
-use std::io::Error;
+use crate::editor::vim::Vim;
+use crossterm::event::{Event, KeyEvent, KeyEventKind};
+use std::collections::VecDeque;
+use std::io::{stdin, Error, IsTerminal};
 use std::panic::{set_hook, take_hook};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long we wait for an event before doing background work, like loading a big file
+const TICK: Duration = Duration::from_millis(100);
+/// Two clicks at the same position within this delay make a double click
+const DOUBLE_CLICK_DELAY: Duration = Duration::from_millis(400);
+/// How often the text of the modified views is written to their swap files
+const SWAP_INTERVAL: Duration = Duration::from_secs(2);
+const RECOVER_PROMPT: &str = "Swap file found: [r]ecover, [d]iff or [x] discard it? ";
+/// How often the files of the views are checked for changes made by other programs
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const CHANGED_PROMPT: &str = "File changed on disk: [k]eep mine, [t]ake theirs or [d]iff? ";
+/// The lines containing a text are shown, or the ones matching a regular expression between slashes, `!` inverts it
+const FILTER_PROMPT: &str = "Filter lines (text, /regex/, !not): ";
+
+/// A set of key bindings replacing the default ones
+pub enum Profile {
+    Vim(Vim),
+    Emacs(Emacs),
+}
+
+impl Profile {
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<EditorCommand> {
+        match self {
+            Self::Vim(vim) => vim.handle_key(key),
+            Self::Emacs(emacs) => emacs.handle_key(key),
+        }
+    }
+
+    /// What the status bar shows: the vim mode, or the emacs prefix being typed
+    fn mode(&self) -> Option<String> {
+        match self {
+            Self::Vim(vim) => Some(vim.mode().to_string()),
+            Self::Emacs(emacs) => emacs.pending_keys(),
+        }
+    }
+
+    fn cursor_shape(&self) -> CursorShape {
+        match self {
+            Self::Vim(vim) => vim.mode().cursor_shape(),
+            Self::Emacs(_) => CursorShape::Default,
+        }
+    }
+}
+
+/// What the command bar asks for
+#[derive(Clone)]
+enum Prompt {
+    GoToLine,
+    /// The file name to save a view without a file to
+    SaveAs,
+    /// What to do with the swap file left for the file of the view: recover it, show its differences or discard it
+    Recover(PathBuf),
+    /// Whether to save the modified views before quitting
+    Quit,
+    /// What to do with the modified text of the view, whose file was changed by another program:
+    /// keep it, take the file instead or show their differences
+    ChangedOnDisk(PathBuf),
+    /// The pattern of the lines to show in the view
+    Filter,
+}
 
 /// This represents our Editor
 /// It manages all the events and printing that happen in the terminal
-/// It relies on our Terminal and the functions of the crossterm crate to work
-pub struct Editor {
+/// It relies on a [`Backend`], by default our Terminal and the functions of the crossterm crate, to work
+pub struct Editor<B: Backend = Terminal> {
     should_quit: bool,
     view: View,
+    /// The views of the other open files, in the order they come after the current one
+    other_views: VecDeque<View>,
+    /// The index of the current view among all the open ones
+    view_index: usize,
+    status_bar: StatusBar,
+    screen: Screen,
+    backend: B,
+    last_click: Option<(Instant, Position)>,
+    /// Keys go straight to commands without a profile
+    profile: Option<Profile>,
+    cursor_shape: CursorShape,
+    macros: Macros,
+    /// The open prompt, keys are typed in its command bar
+    prompt: Option<(Prompt, CommandBar)>,
+    /// The current view, while the differences with its swap file are shown in its place
+    hidden_view: Option<View>,
+    swaps_written_at: Instant,
+    disk_checked_at: Instant,
+    /// Set when the editor is asked to stop by a signal, like `SIGTERM` or `SIGHUP` when the terminal is closed
+    is_terminating: Arc<AtomicBool>,
 }
 
 impl Editor {
     /// # Errors
     ///
     /// Will return `Err` if [`Terminal::set_up`] fails
-    pub fn new() -> Result<Self, Error> {
+    pub fn new(options: &Options) -> Result<Self, Error> {
+        let is_terminating = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&is_terminating);
+        // The handler can only be set once: a second editor does not get one
+        let _ = ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst));
         let current_hook = take_hook();
         set_hook(Box::new(move |panic_info| {
+            swap::flush_all();
             let _ = Terminal::terminate();
             current_hook(panic_info);
         }));
-        Terminal::set_up()?;
-        let mut view = View::default();
-        let args: Vec<String> = std::env::args().collect();
-        if let Some(first_arg) = args.get(1) {
-            view.load(first_arg);
+        let mut editor = Self::with_backend(Terminal::default(), options)?;
+        editor.is_terminating = is_terminating;
+        if let Some(macros) = Macros::default_path().and_then(|path| Macros::load(&path).ok()) {
+            editor.macros = macros;
         }
-        Ok(Self {
+        Ok(editor)
+    }
+}
+
+impl<B: Backend> Editor<B> {
+    /// Creates an editor drawn on `backend`, with a view for each file of `options`.
+    /// The caret is put where the options say, once the view has its size.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Backend::set_up`] or [`Backend::size`] fail
+    pub fn with_backend(mut backend: B, options: &Options) -> Result<Self, Error> {
+        let mut views: VecDeque<View> = options
+            .files
+            .iter()
+            .map(|file| {
+                let mut view = View::default();
+                match &file.source {
+                    Source::Path(path) => view.load(path),
+                    // Without a pipe, there is nothing to read and the view stays empty.
+                    // Keys do not come from the pipe but from the terminal, see [`Terminal::set_up`].
+                    Source::Stdin if !stdin().is_terminal() => view.stream(stdin()),
+                    Source::Stdin => {}
+                }
+                view
+            })
+            .collect();
+        backend.set_up()?;
+        let size = backend.size()?;
+        let mut editor = Self {
             should_quit: false,
-            view,
-        })
+            view: views.pop_front().unwrap_or_default(),
+            other_views: views,
+            view_index: 0,
+            status_bar: StatusBar::default(),
+            screen: Screen::default(),
+            backend,
+            last_click: None,
+            profile: match options.key_bindings {
+                KeyBindings::Default => None,
+                KeyBindings::Vim => Some(Profile::Vim(Vim::default())),
+                KeyBindings::Emacs => Some(Profile::Emacs(Emacs::default())),
+            },
+            cursor_shape: CursorShape::Default,
+            macros: Macros::default(),
+            prompt: None,
+            hidden_view: None,
+            swaps_written_at: Instant::now(),
+            disk_checked_at: Instant::now(),
+            is_terminating: Arc::default(),
+        };
+        editor.resize(size);
+        let mut message = None;
+        for (index, view) in editor.views_mut().enumerate() {
+            if options.is_read_only {
+                view.set_read_only(true);
+            }
+            view.set_keeps_backup(options.keeps_backups);
+            message = message.or_else(|| view.take_message());
+            if options.follows {
+                view.handle_command(EditorCommand::ToggleFollow);
+                message = message.or_else(|| view.take_message());
+            }
+            if let Some(target) = options.files.get(index).and_then(|file| file.position) {
+                view.handle_command(EditorCommand::GoTo(target));
+            }
+        }
+        editor.status_bar.set_message(message);
+        editor.check_swap();
+        Ok(editor)
+    }
+
+    /// The open views, the current one first
+    fn views(&self) -> impl Iterator<Item = &View> {
+        std::iter::once(&self.view).chain(self.other_views.iter())
+    }
+
+    fn views_mut(&mut self) -> impl Iterator<Item = &mut View> {
+        std::iter::once(&mut self.view).chain(self.other_views.iter_mut())
+    }
+
+    #[must_use]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// The views take the whole screen except the last row, which is used by the status bar
+    fn resize(&mut self, size: Size) {
+        let view_size = Size {
+            height: size.height.saturating_sub(1),
+            width: size.width,
+        };
+        self.view.resize(view_size);
+        for view in &mut self.other_views {
+            view.resize(view_size);
+        }
+        self.status_bar.resize(size);
+        if let Some((_, command_bar)) = self.prompt.as_mut() {
+            command_bar.resize(size);
+        }
+        self.screen.resize(size);
     }
 
     /// # Panics
     ///
-    /// Will panic is [`crossterm::event::read`] returns an Error
+    /// Will panic is [`Backend::poll_event`] returns an Error
     pub fn run(&mut self) {
         loop {
+            if self.is_terminating.load(Ordering::SeqCst) {
+                // The modified texts are kept in their swap files, to be recovered
+                self.update_swaps(true);
+                break;
+            }
             self.refresh_screen();
             if self.should_quit {
                 break;
             }
-            match read() {
-                Ok(event) => {
+            match self.backend.poll_event(TICK) {
+                Ok(Some(event)) => {
                     self.evaluate_event(event);
                 }
+                Ok(None) => {}
                 Err(err) => {
                     #[cfg(debug_assertions)]
                     {
@@ -58,42 +277,971 @@ impl Editor {
                     }
                 }
             }
+            self.poll_views();
+            self.update_swaps(false);
+            self.check_swap();
+            if self.disk_checked_at.elapsed() >= DISK_CHECK_INTERVAL {
+                self.disk_checked_at = Instant::now();
+                self.check_disk();
+            }
+        }
+    }
+
+    /// Takes the lines read in the background and the ones written to the followed files
+    fn poll_views(&mut self) {
+        for view in self.views_mut() {
+            view.poll_loading();
+        }
+        if let Some(message) = self.view.poll_following() {
+            self.status_bar.set_message(Some(message.to_string()));
+        }
+        for view in &mut self.other_views {
+            view.poll_following();
         }
     }
 
+    /// Gives the text of the views to their swap files and writes them, every [`SWAP_INTERVAL`] or if `now`.
+    /// The text is only built then, building it at each tick would go through every line after each key.
+    fn update_swaps(&mut self, now: bool) {
+        if !now && self.swaps_written_at.elapsed() < SWAP_INTERVAL {
+            return;
+        }
+        self.swaps_written_at = Instant::now();
+        let hidden_view = self.hidden_view.as_mut();
+        let views = std::iter::once(&mut self.view).chain(self.other_views.iter_mut());
+        for view in views.chain(hidden_view) {
+            view.update_swap();
+            let _ = view.write_swap();
+        }
+    }
+
+    /// Asks what to do with the swap file of the current view if there is one, unless a prompt is open
+    fn check_swap(&mut self) {
+        if self.prompt.is_some() {
+            return;
+        }
+        if let Some(path) = self.view.check_swap() {
+            self.open_prompt(Prompt::Recover(path), RECOVER_PROMPT);
+        }
+    }
+
+    /// Reloads the views whose file changed on disk if their text is not modified.
+    /// If it is modified, asks what to do for the current view, the other ones are asked about once shown.
+    fn check_disk(&mut self) {
+        if self.prompt.is_some() {
+            return;
+        }
+        for view in &mut self.other_views {
+            if view.is_changed_on_disk() && !view.is_modified() {
+                let _ = view.reload();
+            }
+        }
+        if !self.view.is_changed_on_disk() {
+            return;
+        }
+        if self.view.is_modified() {
+            self.ask_about_disk_changes();
+        } else if let Err(error) = self.view.reload() {
+            self.status_bar.set_message(Some(error.to_string()));
+        } else {
+            let message = "Reloaded, the file changed on disk";
+            self.status_bar.set_message(Some(message.to_string()));
+        }
+    }
+
+    fn ask_about_disk_changes(&mut self) {
+        if let Some(file_name) = self.view.get_status().file_name {
+            self.open_prompt(Prompt::ChangedOnDisk(file_name.into()), CHANGED_PROMPT);
+        }
+    }
+
+    #[must_use]
+    pub fn should_quit(&self) -> bool {
+        self.should_quit
+    }
+
     #[allow(clippy::needless_pass_by_value)]
-    fn evaluate_event(&mut self, event: Event) {
+    pub fn evaluate_event(&mut self, event: Event) {
         let should_process = match event {
             Event::Key(KeyEvent { kind, .. }) => kind == KeyEventKind::Press,
-            Event::Resize(_, _) => true,
+            Event::Resize(_, _) | Event::Mouse(_) => true,
             _ => false,
         };
 
         if should_process {
-            if let Ok(command) = EditorCommand::try_from(event) {
-                if matches!(command, EditorCommand::Quit) {
-                    self.should_quit = true;
-                } else {
-                    self.view.handle_command(command);
+            if matches!(event, Event::Key(_)) {
+                self.status_bar.set_message(None);
+            }
+            if let (Some((prompt, command_bar)), Event::Key(key)) = (self.prompt.as_mut(), &event) {
+                let prompt = prompt.clone();
+                match command_bar.handle_key(*key) {
+                    Answer::Typing => {}
+                    Answer::Submitted(value) => self.answer_prompt(prompt, &value),
+                    Answer::Cancelled => {
+                        self.close_prompt();
+                        // Not answering is keeping the text, not to be asked again until the file changes again
+                        if let Prompt::ChangedOnDisk(_) = prompt {
+                            self.view.ignore_disk_changes();
+                        }
+                    }
+                }
+                return;
+            }
+            if let (Some(profile), Event::Key(key)) = (self.profile.as_mut(), &event) {
+                for command in profile.handle_key(*key) {
+                    self.handle_command(command);
+                }
+                return;
+            }
+            match EditorCommand::try_from(event) {
+                Ok(EditorCommand::Click(position)) => {
+                    let command = self.detect_double_click(position);
+                    self.handle_command(command);
+                }
+                Ok(command) => self.handle_command(command),
+                Err(_) => {}
+            }
+        }
+    }
+
+    fn detect_double_click(&mut self, position: Position) -> EditorCommand {
+        let now = Instant::now();
+        let is_double_click = self.last_click.is_some_and(|(time, last_position)| {
+            last_position == position && now.duration_since(time) <= DOUBLE_CLICK_DELAY
+        });
+        if is_double_click {
+            self.last_click = None;
+            EditorCommand::DoubleClick(position)
+        } else {
+            self.last_click = Some((now, position));
+            EditorCommand::Click(position)
+        }
+    }
+
+    pub fn handle_command(&mut self, command: EditorCommand) {
+        self.macros.record(&command);
+        match command {
+            EditorCommand::Quit => self.quit(),
+            EditorCommand::Resize(size) => self.resize(size),
+            EditorCommand::StartRecording(slot) => self.macros.start_recording(slot),
+            EditorCommand::StopRecording => self.stop_recording(),
+            EditorCommand::PlayMacro { slot, times } => self.play_macro(slot, times),
+            EditorCommand::NextBuffer => self.switch_view(true),
+            EditorCommand::PreviousBuffer => self.switch_view(false),
+            EditorCommand::PromptGoToLine => self.open_prompt(Prompt::GoToLine, "Go to line: "),
+            EditorCommand::PromptFilter if self.view.is_filtered() => self.view.clear_filter(),
+            EditorCommand::PromptFilter => self.open_prompt(Prompt::Filter, FILTER_PROMPT),
+            EditorCommand::Save => self.save(),
+            EditorCommand::Suspend => self.suspend(),
+            _ => {
+                self.view.handle_command(command);
+                if let Some(message) = self.view.take_message() {
+                    self.status_bar.set_message(Some(message));
+                }
+            }
+        }
+    }
+
+    /// Stops the editor until the shell resumes it, everything is drawn again then
+    fn suspend(&mut self) {
+        if self.backend.suspend().is_err() {
+            return;
+        }
+        self.cursor_shape = CursorShape::Default;
+        if let Ok(size) = self.backend.size() {
+            self.resize(size);
+        }
+    }
+
+    /// Quits, unless views are modified: their files are listed in a prompt asking whether to save them first
+    fn quit(&mut self) {
+        let modified: Vec<String> = self
+            .views()
+            .filter(|view| view.is_modified())
+            .map(|view| view.get_status().file_name_to_string())
+            .collect();
+        if modified.is_empty() {
+            self.should_quit = true;
+            return;
+        }
+        let text = format!(
+            "Modified: {}. [s]ave, [d]iscard or [c]ancel? ",
+            modified.join(", ")
+        );
+        self.open_prompt(Prompt::Quit, &text);
+    }
+
+    /// Saves every modified view, and quits if they all could be saved
+    fn save_all_and_quit(&mut self) {
+        let mut errors = Vec::new();
+        for view in self.views_mut().filter(|view| view.is_modified()) {
+            let file_name = view.get_status().file_name_to_string();
+            if !view.has_file() {
+                errors.push(format!("{file_name} has no file, save it with Ctrl-S"));
+            } else if let Err(error) = view.save() {
+                errors.push(error.to_string());
+            }
+        }
+        match errors.first() {
+            Some(error) => self.status_bar.set_message(Some(error.clone())),
+            None => self.should_quit = true,
+        }
+    }
+
+    /// Shows the view of the next open file, or of the previous one if not `forward`
+    fn switch_view(&mut self, forward: bool) {
+        let next = if forward {
+            self.other_views.pop_front()
+        } else {
+            self.other_views.pop_back()
+        };
+        let Some(mut next) = next else {
+            return;
+        };
+        self.view.move_kill_ring_to(&mut next);
+        next.set_needs_redraw();
+        let previous = std::mem::replace(&mut self.view, next);
+        let count = self.other_views.len().saturating_add(2);
+        self.view_index = if forward {
+            self.other_views.push_back(previous);
+            self.view_index.saturating_add(1)
+        } else {
+            self.other_views.push_front(previous);
+            self.view_index.saturating_add(count).saturating_sub(1)
+        }
+        .checked_rem(count)
+        .unwrap_or_default();
+        self.check_swap();
+    }
+
+    /// Saves the current view, asking for a file name first if it has no file
+    /// A file changed by another program is not written over without asking first.
+    fn save(&mut self) {
+        if !self.view.has_file() {
+            self.open_prompt(Prompt::SaveAs, "Save as: ");
+        } else if self.view.is_changed_on_disk() {
+            self.ask_about_disk_changes();
+        } else if let Err(error) = self.view.save() {
+            self.status_bar.set_message(Some(error.to_string()));
+        }
+    }
+
+    fn open_prompt(&mut self, prompt: Prompt, text: &str) {
+        self.prompt = Some((prompt, CommandBar::new(text, self.screen.size())));
+    }
+
+    fn close_prompt(&mut self) {
+        self.prompt = None;
+        self.status_bar.set_needs_redraw();
+        if let Some(view) = self.hidden_view.take() {
+            self.view = view;
+            self.view.set_needs_redraw();
+        }
+    }
+
+    /// Runs what the prompt asked for. The prompt stays open if the answer is not valid.
+    fn answer_prompt(&mut self, prompt: Prompt, value: &str) {
+        match prompt {
+            Prompt::GoToLine => {
+                if let Ok(target) = value.parse::<GoToTarget>() {
+                    self.close_prompt();
+                    self.handle_command(EditorCommand::GoTo(target));
+                }
+            }
+            Prompt::SaveAs => {
+                if value.is_empty() {
+                    return;
+                }
+                self.close_prompt();
+                if let Err(error) = self.view.save_as(value) {
+                    self.status_bar.set_message(Some(error.to_string()));
+                }
+            }
+            Prompt::Filter => {
+                if value.is_empty() {
+                    return;
+                }
+                self.close_prompt();
+                if let Err(error) = self.view.filter(value) {
+                    self.status_bar.set_message(Some(error));
+                }
+            }
+            Prompt::Recover(path) => self.answer_recover(&path, value),
+            Prompt::ChangedOnDisk(path) => self.answer_changed_on_disk(&path, value),
+            Prompt::Quit => {
+                if matches!(value, "s" | "d" | "c") {
+                    self.close_prompt();
+                }
+                match value {
+                    "s" => self.save_all_and_quit(),
+                    "d" => self.should_quit = true,
+                    _ => {}
                 }
             }
         }
     }
 
-    fn refresh_screen(&mut self) {
-        let _ = Terminal::hide_cursor();
-        self.view.render();
-        let _ = Terminal::move_cursor_to(self.view.caret_position());
-        let _ = Terminal::show_cursor();
-        let _ = Terminal::execute();
+    /// Shows the differences between the text of the current view and the file `path` in its place,
+    /// while `prompt` asks what to do with them
+    fn show_diff(&mut self, path: &Path, prompt: Prompt, text: &str) {
+        if self.hidden_view.is_none() {
+            if let Ok(diff) = self.view.diff_with(path) {
+                self.hidden_view = Some(std::mem::replace(&mut self.view, diff));
+            }
+        }
+        self.open_prompt(prompt, text);
+    }
+
+    /// Keeps the text, to be written over the file when saved, or replaces it with the file,
+    /// or shows their differences and asks again
+    fn answer_changed_on_disk(&mut self, path: &Path, value: &str) {
+        match value {
+            "k" => {
+                self.close_prompt();
+                self.view.ignore_disk_changes();
+            }
+            "t" => {
+                self.close_prompt();
+                if let Err(error) = self.view.reload() {
+                    self.status_bar.set_message(Some(error.to_string()));
+                }
+            }
+            "d" => {
+                let prompt = Prompt::ChangedOnDisk(path.to_path_buf());
+                self.show_diff(path, prompt, CHANGED_PROMPT);
+            }
+            _ => {}
+        }
+    }
+
+    /// Recovers or discards the swap file `path` and closes the prompt,
+    /// or shows its differences with the text in place of the view and asks again
+    fn answer_recover(&mut self, path: &Path, value: &str) {
+        match value {
+            "r" => {
+                self.close_prompt();
+                let _ = self.view.recover(path);
+            }
+            "d" => self.show_diff(path, Prompt::Recover(path.to_path_buf()), RECOVER_PROMPT),
+            "x" => {
+                self.close_prompt();
+                let _ = std::fs::remove_file(path);
+            }
+            _ => {}
+        }
+    }
+
+    /// Ends the recording of a macro, telling if the macros could not be saved
+    fn stop_recording(&mut self) {
+        if let Err(error) = self.macros.stop_recording() {
+            self.status_bar
+                .set_message(Some(format!("The macros could not be saved: {error}")));
+        }
+    }
+
+    fn play_macro(&mut self, slot: Option<char>, times: usize) {
+        if slot.is_none() && self.macros.recording_slot().is_some() {
+            self.stop_recording();
+            return;
+        }
+        let commands = self.macros.commands(slot);
+        for _ in 0..times {
+            for command in &commands {
+                self.handle_command(command.clone());
+            }
+        }
+    }
+
+    pub fn refresh_screen(&mut self) {
+        let mut status = self.view.get_status();
+        if !self.other_views.is_empty() {
+            status.buffer = Some((
+                self.view_index.saturating_add(1),
+                self.other_views.len().saturating_add(1),
+            ));
+        }
+        let mode = self.profile.as_ref().and_then(Profile::mode);
+        let recording = self
+            .macros
+            .recording_slot()
+            .map(|slot| format!("recording @{slot}"));
+        status.mode = match (mode, recording) {
+            (Some(mode), Some(recording)) => Some(format!("{mode} {recording}")),
+            (mode, recording) => mode.or(recording),
+        };
+        self.status_bar.update_status(status);
+        let _ = self.backend.hide_cursor();
+        self.view.render(&mut self.screen);
+        if let Some((_, command_bar)) = self.prompt.as_mut() {
+            command_bar.render(&mut self.screen);
+        } else {
+            self.status_bar.render(&mut self.screen);
+        }
+        let result = self.screen.present(&mut self.backend);
+        debug_assert!(result.is_ok(), "Failed to render the screen");
+        let cursor_shape = self
+            .profile
+            .as_ref()
+            .map_or(CursorShape::Default, Profile::cursor_shape);
+        if cursor_shape != self.cursor_shape {
+            let _ = self.backend.set_cursor_shape(cursor_shape);
+            self.cursor_shape = cursor_shape;
+        }
+        if let Some((_, command_bar)) = &self.prompt {
+            let _ = self.backend.move_cursor_to(command_bar.caret_position());
+            let _ = self.backend.show_cursor();
+        } else if self.view.is_caret_visible() {
+            let _ = self.backend.move_cursor_to(self.view.caret_position());
+            let _ = self.backend.show_cursor();
+        }
+        let _ = self.backend.execute();
     }
 }
 
-impl Drop for Editor {
+impl<B: Backend> Drop for Editor<B> {
     fn drop(&mut self) {
-        let _ = Terminal::terminate();
+        let _ = self.backend.terminate();
         if self.should_quit {
-            let _ = Terminal::print("Goodbye ! ~~");
+            for view in self.views_mut() {
+                let _ = view.remove_swap();
+            }
+            let _ = self.backend.print("Goodbye ! ~~");
+            let _ = self.backend.execute();
+            return;
+        }
+        // Texts without a file are only offered again to an editor started without a file, tell where they are
+        let paths: Vec<PathBuf> = self.views().filter_map(View::unnamed_swap_path).collect();
+        for path in paths {
+            let _ = self
+                .backend
+                .print(&format!("The text is kept in {}\n", path.display()));
         }
+        let _ = self.backend.execute();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::backend::memory::MemoryBackend;
+    use crate::editor::terminal::Position;
+    use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+    pub(super) fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    /// An editor drawn on `width` columns and 10 rows, started with the command line arguments `args`
+    pub(super) fn set_up(width: usize, args: &[&str]) -> Editor<MemoryBackend> {
+        let backend = MemoryBackend::new(Size { width, height: 10 });
+        let args: Vec<String> = std::iter::once("text-editor")
+            .chain(args.iter().copied())
+            .map(ToString::to_string)
+            .collect();
+        Editor::with_backend(backend, &Options::parse(&args).unwrap()).unwrap()
+    }
+
+    /// The text of the screen row `row`, without the spaces after it
+    pub(super) fn row(editor: &Editor<MemoryBackend>, row: usize) -> String {
+        editor.backend().row_text(row).trim_end().to_string()
+    }
+
+    /// Types `text`, a key for each character
+    fn type_text(editor: &mut Editor<MemoryBackend>, text: &str) {
+        for character in text.chars() {
+            editor.evaluate_event(key(KeyCode::Char(character), KeyModifiers::NONE));
+        }
+    }
+
+    /// Types `text` in the prompt and validates it
+    fn answer(editor: &mut Editor<MemoryBackend>, text: &str) {
+        type_text(editor, text);
+        editor.evaluate_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        editor.refresh_screen();
+    }
+
+    /// A directory of its own for a test, removed with its files when the test ends, even if it fails
+    pub(super) struct TempDir(PathBuf);
+
+    impl TempDir {
+        /// Creates `text-editor-{name}-{pid}` in the temporary directory, `name` being unique to the test
+        pub(super) fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("text-editor-{name}-{}", std::process::id()));
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        pub(super) fn path(&self) -> &Path {
+            &self.0
+        }
+
+        pub(super) fn join(&self, file_name: &str) -> PathBuf {
+            self.0.join(file_name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_run_scripted_events() {
+        let mut editor = set_up(40, &["./text-test/test.txt"]);
+        editor.backend_mut().push_events([
+            key(KeyCode::Down, KeyModifiers::NONE),
+            key(KeyCode::End, KeyModifiers::NONE),
+            key(KeyCode::Char('?'), KeyModifiers::NONE),
+            key(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            key(KeyCode::Char('d'), KeyModifiers::NONE),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ]);
+        editor.run();
+        assert!(editor.should_quit());
+        assert!(editor.backend().row_text(1).starts_with("rust is 𝒻𝒶𝓃𝒸𝓎!?"));
+        assert_eq!(
+            editor.backend().cursor_position(),
+            Position { col: 15, row: 1 }
+        );
+        assert!(editor
+            .backend()
+            .row_text(9)
+            .starts_with("./text-test/test.txt - 4 lines"));
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut editor = set_up(40, &["./text-test/test.txt"]);
+        editor.refresh_screen();
+        editor.backend_mut().resize(Size {
+            width: 20,
+            height: 5,
+        });
+        editor
+            .backend_mut()
+            .push_events([key(KeyCode::Char('q'), KeyModifiers::CONTROL)]);
+        editor.run();
+        assert_eq!(editor.backend().row_text(0), "rust is great!      ");
+        assert_eq!(editor.backend().row_text(4), "./text-test/test.txt");
+    }
+
+    #[test]
+    fn test_double_click() {
+        let mut editor = set_up(40, &["./text-test/test.txt"]);
+        let click = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 9,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        });
+        editor.evaluate_event(click.clone());
+        editor.refresh_screen();
+        let is_selected = |editor: &Editor<MemoryBackend>, col| {
+            editor
+                .backend()
+                .cell(Position { col, row: 0 })
+                .unwrap()
+                .style
+                .reverse
+        };
+        assert!(!is_selected(&editor, 9));
+        editor.evaluate_event(click);
+        editor.refresh_screen();
+        assert!((8..13).all(|col| is_selected(&editor, col)));
+        assert!(!is_selected(&editor, 13));
+    }
+
+    #[test]
+    fn test_go_to_line() {
+        let mut editor = set_up(40, &["./text-test/test-4.txt"]);
+        editor.evaluate_event(key(KeyCode::Char('g'), KeyModifiers::CONTROL));
+        type_text(&mut editor, "20:5");
+        editor.refresh_screen();
+        assert_eq!(editor.backend().row_text(9).trim_end(), "Go to line: 20:5");
+        assert_eq!(
+            editor.backend().cursor_position(),
+            Position { col: 16, row: 9 }
+        );
+        editor.evaluate_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(
+            editor.backend().cursor_position(),
+            Position { col: 4, row: 4 }
+        );
+        assert!(editor.backend().row_text(4).starts_with("    /// # Errors"));
+        assert!(editor.backend().row_text(9).ends_with("20/99"));
+        editor.evaluate_event(key(KeyCode::Char('g'), KeyModifiers::CONTROL));
+        type_text(&mut editor, "-4x");
+        editor.evaluate_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(editor.backend().row_text(9).trim_end(), "Go to line: -4x");
+        editor.evaluate_event(key(KeyCode::Backspace, KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert!(editor.backend().row_text(9).ends_with("16/99"));
+        editor.evaluate_event(key(KeyCode::Char('g'), KeyModifiers::CONTROL));
+        type_text(&mut editor, "100%");
+        editor.evaluate_event(key(KeyCode::Esc, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert!(editor.backend().row_text(9).ends_with("16/99"));
+    }
+
+    #[test]
+    fn test_save_as() {
+        let mut editor = set_up(40, &[]);
+        let directory = TempDir::new("save-as");
+        let path = directory.join("notes");
+        let file_name = path.to_str().unwrap();
+        type_text(&mut editor, "hi");
+        editor.evaluate_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        answer(&mut editor, "");
+        assert_eq!(editor.backend().row_text(9).trim_end(), "Save as:");
+        answer(&mut editor, "/missing/x");
+        assert!(editor
+            .backend()
+            .row_text(9)
+            .starts_with("Could not create /missing/.x."));
+        editor.evaluate_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        editor.refresh_screen();
+        assert_eq!(editor.backend().row_text(9).trim_end(), "Save as:");
+        answer(&mut editor, file_name);
+        assert!(editor.backend().row_text(9).starts_with(file_name));
+        type_text(&mut editor, "!");
+        editor.evaluate_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hi!\n");
+    }
+
+    #[test]
+    fn test_recover_swap_file() {
+        let directory = TempDir::new("recover");
+        let path = directory.join("notes.txt");
+        std::fs::write(&path, "saved\n").unwrap();
+        std::fs::write(directory.join(".notes.txt.swp"), "saved\nunsaved\n").unwrap();
+        let mut editor = set_up(40, &[path.to_str().unwrap()]);
+        editor.refresh_screen();
+        assert!(editor.backend().row_text(9).starts_with("Swap file found"));
+        answer(&mut editor, "d");
+        assert_eq!(editor.backend().row_text(0).trim_end(), "@@ -1,1 +1,2 @@");
+        assert_eq!(editor.backend().row_text(2).trim_end(), "+unsaved");
+        assert!(editor.backend().row_text(9).starts_with("Swap file found"));
+        answer(&mut editor, "r");
+        assert_eq!(editor.backend().row_text(1).trim_end(), "unsaved");
+        editor.handle_command(EditorCommand::Save);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "saved\nunsaved\n");
+        assert!(!directory.join(".notes.txt.swp").exists());
+    }
+
+    #[test]
+    fn test_file_changed_on_disk() {
+        let directory = TempDir::new("changed");
+        let path = directory.join("notes.txt");
+        std::fs::write(&path, "one\n").unwrap();
+        let mut editor = set_up(40, &[path.to_str().unwrap()]);
+        std::fs::write(&path, "two!\n").unwrap();
+        editor.check_disk();
+        editor.refresh_screen();
+        assert_eq!(row(&editor, 0), "two!");
+        assert_eq!(row(&editor, 9), "Reloaded, the file changed on disk");
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        std::fs::write(&path, "three\n").unwrap();
+        editor.check_disk();
+        answer(&mut editor, "d");
+        assert_eq!(row(&editor, 1), "-xtwo!");
+        assert_eq!(row(&editor, 2), "+three");
+        assert!(row(&editor, 9).starts_with("File changed on disk"));
+        answer(&mut editor, "k");
+        editor.evaluate_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        let kept = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, "four\n").unwrap();
+        editor.evaluate_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        editor.refresh_screen();
+        assert!(row(&editor, 9).starts_with("File changed on disk"));
+        answer(&mut editor, "t");
+        assert_eq!(kept, "xtwo!\n");
+        assert_eq!(row(&editor, 0), "four");
+    }
+
+    #[test]
+    fn test_follow_growing_file() {
+        let directory = TempDir::new("follow-file");
+        let path = directory.join("app.log");
+        let lines = |numbers: std::ops::RangeInclusive<usize>| {
+            numbers.fold(String::new(), |text, number| format!("{text}{number}\n"))
+        };
+        std::fs::write(&path, lines(1..=12)).unwrap();
+        let mut editor = set_up(80, &["--follow", path.to_str().unwrap()]);
+        let append = |text: &str| {
+            let mut file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap();
+            std::io::Write::write_all(&mut file, text.as_bytes()).unwrap();
+        };
+        append(&lines(13..=20));
+        editor.poll_views();
+        editor.refresh_screen();
+        assert_eq!(editor.view.get_status().following, Some(true));
+        assert_eq!(editor.backend().row_text(8).trim_end(), "20");
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::Up, KeyModifiers::NONE));
+        append("21\n");
+        editor.poll_views();
+        editor.refresh_screen();
+        assert_eq!(editor.view.get_status().following, Some(false));
+        assert_eq!(editor.backend().row_text(8).trim_end(), "20");
+        assert_eq!(editor.view.get_status().current_line_index, 18);
+        std::fs::write(&path, "new\n").unwrap();
+        editor.poll_views();
+        editor.refresh_screen();
+        assert_eq!(editor.backend().row_text(0).trim_end(), "new");
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "The file was truncated, following it from the start"
+        );
+        editor.evaluate_event(key(KeyCode::Char('f'), KeyModifiers::ALT));
+        assert_eq!(editor.view.get_status().following, None);
+        assert!(!editor.view.is_modified());
+    }
+
+    #[test]
+    fn test_filter_lines() {
+        let directory = TempDir::new("filter");
+        let path = directory.join("numbers.txt");
+        let numbers = (1..=20).fold(String::new(), |text, number| format!("{text}{number}\n"));
+        std::fs::write(&path, numbers).unwrap();
+        let mut editor = set_up(40, &[path.to_str().unwrap()]);
+        editor.evaluate_event(key(KeyCode::Char('l'), KeyModifiers::ALT));
+        type_text(&mut editor, "/^1[05]$/");
+        editor.evaluate_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(editor.backend().row_text(0).trim_end(), "10 10");
+        assert_eq!(editor.backend().row_text(1).trim_end(), "15 15");
+        assert_eq!(editor.backend().row_text(2).trim_end(), "~");
+        assert_eq!(
+            editor.backend().cursor_position(),
+            Position { col: 3, row: 0 }
+        );
+        editor.evaluate_event(key(KeyCode::Down, KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::End, KeyModifiers::NONE));
+        type_text(&mut editor, "!");
+        editor.evaluate_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(editor.backend().row_text(1).trim_end(), "15 15!");
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "Lines can not be added while filtering"
+        );
+        assert_eq!(editor.view.get_status().current_line_index, 14);
+        assert!(editor.view.is_modified());
+        editor.evaluate_event(key(KeyCode::Char('l'), KeyModifiers::ALT));
+        editor.refresh_screen();
+        assert!(!editor.view.is_filtered());
+        assert_eq!(editor.view.get_status().current_line_index, 14);
+        assert!(editor.view.get_status().shown_lines.is_none());
+        let caret = editor.backend().cursor_position();
+        assert_eq!(editor.backend().row_text(caret.row).trim_end(), "15!");
+        editor.evaluate_event(key(KeyCode::Char('l'), KeyModifiers::ALT));
+        type_text(&mut editor, "!/./");
+        editor.evaluate_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "No line matches !/./"
+        );
+    }
+
+    #[test]
+    fn test_edit_binary_file() {
+        let directory = TempDir::new("binary");
+        let path = directory.join("program.bin");
+        std::fs::write(&path, b"\x7fELF\0\x01text\n").unwrap();
+        let mut editor = set_up(80, &[path.to_str().unwrap()]);
+        editor.refresh_screen();
+        assert_eq!(
+            editor.backend().row_text(0).trim_end(),
+            "00000000  7f 45 4c 46 00 01 74 65  78 74 0a                 |.ELF..text.|"
+        );
+        assert!(editor.backend().row_text(9).contains("11 bytes"));
+        editor.evaluate_event(key(KeyCode::Right, KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::Char('g'), KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "Type a hex digit, from 0 to 9 or a to f"
+        );
+        editor.evaluate_event(key(KeyCode::Char('6'), KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::Char('5'), KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::Backspace, KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "Bytes can only be overwritten with hex digits"
+        );
+        assert_eq!(
+            editor.backend().cursor_position(),
+            Position { col: 16, row: 0 }
+        );
+        assert!(editor
+            .backend()
+            .row_text(0)
+            .trim_end()
+            .ends_with("|.eLF..text.|"));
+        editor.evaluate_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::ALT));
+        editor.refresh_screen();
+        assert_eq!(std::fs::read(&path).unwrap(), b"\x7feLF\0\x01text\n");
+        assert!(!editor.view.is_modified());
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "The bytes are not text"
+        );
+    }
+
+    #[test]
+    fn test_quit_with_modified_views() {
+        let size = Size {
+            width: 80,
+            height: 10,
+        };
+        let args = [
+            "text-editor",
+            "./text-test/test.txt",
+            "./text-test/test-2.txt",
+        ]
+        .map(ToString::to_string);
+        let options = Options::parse(&args).unwrap();
+        let mut editor = Editor::with_backend(MemoryBackend::new(size), &options).unwrap();
+        let quit = key(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        for _ in 0..2 {
+            editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+            editor.evaluate_event(key(KeyCode::PageDown, KeyModifiers::CONTROL));
+        }
+        editor.evaluate_event(quit.clone());
+        editor.refresh_screen();
+        assert!(editor.backend().row_text(9).starts_with(
+            "Modified: ./text-test/test.txt, ./text-test/test-2.txt. [s]ave, [d]iscard or"
+        ));
+        answer(&mut editor, "c");
+        assert!(!editor.should_quit());
+        editor.evaluate_event(quit.clone());
+        answer(&mut editor, "d");
+        assert!(editor.should_quit());
+
+        let mut editor =
+            Editor::with_backend(MemoryBackend::new(size), &Options::default()).unwrap();
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        editor.evaluate_event(quit);
+        answer(&mut editor, "s");
+        assert!(!editor.should_quit());
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "[No Name] has no file, save it with Ctrl-S"
+        );
+    }
+
+    #[test]
+    fn test_suspend_and_terminate() {
+        let directory = TempDir::new("terminate");
+        let path = directory.join("notes.txt");
+        std::fs::write(&path, "text\n").unwrap();
+        let mut editor = set_up(40, &[path.to_str().unwrap()]);
+        editor.evaluate_event(key(KeyCode::Char('!'), KeyModifiers::NONE));
+        editor.refresh_screen();
+        editor.evaluate_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        editor.refresh_screen();
+        assert_eq!(editor.backend().suspend_count(), 1);
+        assert_eq!(editor.backend().row_text(0).trim_end(), "!text");
+        editor.is_terminating.store(true, Ordering::SeqCst);
+        editor.run();
+        assert!(!editor.should_quit());
+        drop(editor);
+        let swap = std::fs::read_to_string(directory.join(".notes.txt.swp")).unwrap();
+        assert_eq!(swap, "!text\n");
+    }
+
+    #[test]
+    fn test_toggle_read_only() {
+        let mut editor = set_up(80, &["./text-test/test.txt"]);
+        editor.evaluate_event(key(KeyCode::Char('r'), KeyModifiers::ALT));
+        editor.refresh_screen();
+        assert!(editor.backend().row_text(9).contains("[read-only]"));
+        let first_line = editor.backend().row_text(0);
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert_eq!(editor.backend().row_text(0), first_line);
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "The buffer is read-only"
+        );
+        editor.evaluate_event(key(KeyCode::Char('r'), KeyModifiers::ALT));
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert!(!editor.backend().row_text(9).contains("[read-only]"));
+        assert!(editor.backend().row_text(0).starts_with('x'));
+    }
+
+    #[test]
+    fn test_macros_not_saved() {
+        let mut editor = set_up(80, &["./text-test/test.txt"]);
+        let directory = TempDir::new("macros-not-saved");
+        editor.macros = Macros::load(&directory.join("config").join("macros")).unwrap();
+        // The directory of the macros can not be created where a file is
+        std::fs::write(directory.join("config"), "").unwrap();
+        editor.evaluate_event(key(KeyCode::F(3), KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::Down, KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::F(4), KeyModifiers::NONE));
+        editor.refresh_screen();
+        assert!(editor
+            .backend()
+            .row_text(9)
+            .starts_with("The macros could not be saved: "));
+    }
+
+    #[test]
+    fn test_open_files() {
+        let mut editor = set_up(
+            80,
+            &[
+                "--readonly",
+                "./text-test/test-4.txt:20:5",
+                "./text-test/test.txt",
+            ],
+        );
+        editor.refresh_screen();
+        assert_eq!(
+            editor.backend().cursor_position(),
+            Position { col: 4, row: 4 }
+        );
+        let status = editor.backend().row_text(9);
+        assert!(status.starts_with("./text-test/test-4.txt - 99 lines [read-only] [1/2]"));
+        assert!(status.ends_with("20/99"));
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::PageDown, KeyModifiers::CONTROL));
+        editor.refresh_screen();
+        assert!(editor.backend().row_text(0).starts_with("rust is great!"));
+        assert!(editor.backend().row_text(9).contains("[2/2]"));
+        editor.evaluate_event(key(KeyCode::PageDown, KeyModifiers::CONTROL));
+        editor.refresh_screen();
+        assert!(editor.backend().row_text(4).starts_with("    /// # Errors"));
+        assert!(editor.backend().row_text(9).contains("[1/2]"));
+    }
+
+    #[test]
+    fn test_open_missing_and_unreadable_files() {
+        let directory = TempDir::new("new");
+        let path = directory.join("new.rs");
+        let file_name = path.to_str().unwrap();
+        let mut editor = set_up(80, &[file_name, "./text-test"]);
+        editor.refresh_screen();
+        assert_eq!(
+            editor.backend().row_text(9).trim_end(),
+            "Could not open ./text-test: Is a directory (os error 21)"
+        );
+        editor.evaluate_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+        editor.evaluate_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        editor.refresh_screen();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x\n");
     }
 }
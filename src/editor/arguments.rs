@@ -0,0 +1,249 @@
+use crate::editor::editorcommand::{GoToTarget, LineTarget};
+use std::path::Path;
+
+pub const USAGE: &str = "\
+Usage: text-editor [OPTIONS] [+LINE[:COLUMN]] [FILE[:LINE[:COLUMN]]]...
+
+Opens each FILE in its own buffer, `-` being the text piped to the standard input.
+The caret starts at LINE and COLUMN, given after the file name or before it with `+`.
+
+Options:
+  --readonly     Opens the files without allowing to edit them
+  --backup       Copies each file to FILE~ before saving over it
+  --follow       Shows what is written at the end of the files as they grow, like tail -f
+  --vim          Uses vim-style modal key bindings
+  --emacs        Uses emacs key bindings
+  -h, --help     Prints this help
+  -V, --version  Prints the version";
+
+/// The key bindings to use instead of the default ones
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum KeyBindings {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    Path(String),
+    /// The text piped to the standard input, written `-`
+    Stdin,
+}
+
+/// A file to open, with where to put the caret in it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileArgument {
+    pub source: Source,
+    pub position: Option<GoToTarget>,
+}
+
+/// How to run the editor
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    pub files: Vec<FileArgument>,
+    pub is_read_only: bool,
+    /// Files are copied to `file~` before being saved over
+    pub keeps_backups: bool,
+    /// The views follow their files from the start, see [`crate::editor::editorcommand::EditorCommand::ToggleFollow`]
+    pub follows: bool,
+    pub key_bindings: KeyBindings,
+}
+
+/// What the command line asks for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Arguments {
+    Help,
+    Version,
+    Edit(Options),
+}
+
+impl Arguments {
+    /// Parses the command line arguments, program name included
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an option is unknown or a position can not be parsed
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        for arg in args.iter().skip(1).take_while(|arg| *arg != "--") {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Self::Help),
+                "-V" | "--version" => return Ok(Self::Version),
+                _ => {}
+            }
+        }
+        Options::parse(args).map(Self::Edit)
+    }
+}
+
+impl Options {
+    /// Parses the command line arguments, program name included, for the options to edit files with
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an option is unknown or a position can not be parsed
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut position = None;
+        let mut are_options_over = false;
+        for arg in args.iter().skip(1) {
+            let source = match arg.as_str() {
+                _ if are_options_over => Source::Path(arg.clone()),
+                "--" => {
+                    are_options_over = true;
+                    continue;
+                }
+                "--readonly" => {
+                    options.is_read_only = true;
+                    continue;
+                }
+                "--backup" => {
+                    options.keeps_backups = true;
+                    continue;
+                }
+                "--follow" => {
+                    options.follows = true;
+                    continue;
+                }
+                "--vim" => {
+                    options.key_bindings = KeyBindings::Vim;
+                    continue;
+                }
+                "--emacs" => {
+                    options.key_bindings = KeyBindings::Emacs;
+                    continue;
+                }
+                "-" => Source::Stdin,
+                _ if arg.starts_with('+') => {
+                    position = Some(
+                        parse_position(arg.get(1..).unwrap_or_default())
+                            .ok_or_else(|| format!("Invalid position {arg}"))?,
+                    );
+                    continue;
+                }
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+                _ => {
+                    let (path, suffix) = split_position(arg);
+                    position = suffix.or(position);
+                    Source::Path(path.to_string())
+                }
+            };
+            options.files.push(FileArgument {
+                source,
+                position: position.take(),
+            });
+        }
+        Ok(options)
+    }
+}
+
+/// Parses `LINE` or `LINE:COLUMN`, both numbers
+fn parse_position(text: &str) -> Option<GoToTarget> {
+    let (line, column) = match text.split_once(':') {
+        Some((line, column)) => (line, Some(column.parse().ok()?)),
+        None => (text, None),
+    };
+    Some(GoToTarget {
+        line: LineTarget::Number(line.parse().ok()?),
+        column,
+    })
+}
+
+/// Splits `file.rs:42:7` or `file.rs:42` into the file name and the position.
+/// A file whose name really ends like a position is opened as is.
+fn split_position(arg: &str) -> (&str, Option<GoToTarget>) {
+    if Path::new(arg).exists() {
+        return (arg, None);
+    }
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit());
+    let Some((head, last)) = arg.rsplit_once(':') else {
+        return (arg, None);
+    };
+    if !is_number(last) {
+        return (arg, None);
+    }
+    match head.rsplit_once(':') {
+        Some((path, line)) if is_number(line) => (path, parse_position(&format!("{line}:{last}"))),
+        _ => (head, parse_position(last)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Arguments, String> {
+        let args: Vec<String> = ["text-editor"]
+            .iter()
+            .chain(args)
+            .map(ToString::to_string)
+            .collect();
+        Arguments::parse(&args)
+    }
+
+    fn file(path: &str, line: usize, column: Option<usize>) -> FileArgument {
+        FileArgument {
+            source: Source::Path(path.to_string()),
+            position: Some(GoToTarget {
+                line: LineTarget::Number(line),
+                column,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_positions() {
+        let Ok(Arguments::Edit(options)) = parse(&[
+            "src/main.rs:42:7",
+            "+12",
+            "missing.rs",
+            "other.rs:3",
+            "-",
+            "--",
+            "-h",
+        ]) else {
+            panic!("The arguments should be valid");
+        };
+        assert_eq!(
+            options.files,
+            vec![
+                file("src/main.rs", 42, Some(7)),
+                file("missing.rs", 12, None),
+                file("other.rs", 3, None),
+                FileArgument {
+                    source: Source::Stdin,
+                    position: None
+                },
+                FileArgument {
+                    source: Source::Path("-h".to_string()),
+                    position: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        assert_eq!(parse(&["a.rs", "--help"]), Ok(Arguments::Help));
+        assert_eq!(parse(&["-V"]), Ok(Arguments::Version));
+        assert!(parse(&["--wrong"]).is_err());
+        assert!(parse(&["+x", "a.rs"]).is_err());
+        let Ok(Arguments::Edit(options)) =
+            parse(&["--readonly", "--backup", "--follow", "--emacs", "a.rs:b"])
+        else {
+            panic!("The arguments should be valid");
+        };
+        assert!(options.is_read_only);
+        assert!(options.keeps_backups);
+        assert!(options.follows);
+        assert_eq!(options.key_bindings, KeyBindings::Emacs);
+        assert_eq!(
+            options.files,
+            vec![FileArgument {
+                source: Source::Path("a.rs:b".to_string()),
+                position: None
+            }]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use crate::editor::screen::Style;
+use crate::editor::terminal::{CursorShape, Position, Size};
+use crossterm::event::Event;
+use std::io::Error;
+use std::time::Duration;
+
+pub mod memory;
+
+/// This represents where the editor is drawn and where its events come from.
+/// [`crate::editor::terminal::Terminal`] is the real terminal, [`memory::MemoryBackend`] keeps everything in memory
+/// so the editor can be driven without a TTY.
+pub trait Backend {
+    /// # Errors
+    ///
+    /// Will return `Err` if the backend can not be prepared for the editor
+    fn set_up(&mut self) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the backend can not be restored to its original state
+    fn terminate(&mut self) -> Result<(), Error>;
+
+    /// Gives the backend back to what was there before the editor until the editor is resumed,
+    /// then prepares it again. Everything has to be drawn again after.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the backend can not be restored or prepared again
+    fn suspend(&mut self) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the size can not be read
+    fn size(&self) -> Result<Size, Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be queued
+    fn clear_screen(&mut self) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be queued
+    fn move_cursor_to(&mut self, position: Position) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be queued
+    fn set_style(&mut self, style: Style) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be queued
+    fn print(&mut self, string: &str) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be queued
+    fn hide_cursor(&mut self) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be queued
+    fn show_cursor(&mut self) -> Result<(), Error>;
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be queued
+    fn set_cursor_shape(&mut self, shape: CursorShape) -> Result<(), Error>;
+
+    /// Sends everything queued since the last call
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the output can not be flushed
+    fn execute(&mut self) -> Result<(), Error>;
+
+    /// Waits at most `timeout` for the next event, returns `None` if there was none
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the events can not be read
+    fn poll_event(&mut self, timeout: Duration) -> Result<Option<Event>, Error>;
+}
@@ -0,0 +1,202 @@
+use crate::editor::backend::Backend;
+use crate::editor::screen::{Cell, Style};
+use crate::editor::terminal::{CursorShape, Position, Size};
+use crossterm::event::Event;
+use std::collections::VecDeque;
+use std::io::Error;
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A backend that records what is printed in a grid of cells and replays scripted events.
+/// Full-width graphemes take two cells, the second one holding an empty grapheme.
+pub struct MemoryBackend {
+    size: Size,
+    cells: Vec<Cell>,
+    cursor: Position,
+    is_cursor_visible: bool,
+    cursor_shape: CursorShape,
+    style: Style,
+    events: VecDeque<Event>,
+    /// How many times the editor was suspended
+    suspend_count: usize,
+}
+
+impl MemoryBackend {
+    #[must_use]
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            cells: vec![Cell::default(); size.width.saturating_mul(size.height)],
+            cursor: Position::default(),
+            is_cursor_visible: true,
+            cursor_shape: CursorShape::default(),
+            style: Style::default(),
+            events: VecDeque::new(),
+            suspend_count: 0,
+        }
+    }
+
+    /// Adds `events` after the events not read yet
+    pub fn push_events<T: IntoIterator<Item = Event>>(&mut self, events: T) {
+        self.events.extend(events);
+    }
+
+    /// Changes the size of the screen, which is cleared, and sends the matching resize event
+    pub fn resize(&mut self, size: Size) {
+        self.size = size;
+        self.cells = vec![Cell::default(); size.width.saturating_mul(size.height)];
+        self.events.push_back(Event::Resize(
+            u16::try_from(size.width).unwrap_or(u16::MAX),
+            u16::try_from(size.height).unwrap_or(u16::MAX),
+        ));
+    }
+
+    #[must_use]
+    pub fn cell(&self, position: Position) -> Option<&Cell> {
+        if position.col >= self.size.width {
+            return None;
+        }
+        self.cells.get(
+            position
+                .row
+                .saturating_mul(self.size.width)
+                .saturating_add(position.col),
+        )
+    }
+
+    /// Returns the text shown on `row`, trailing spaces included
+    #[must_use]
+    pub fn row_text(&self, row: usize) -> String {
+        (0..self.size.width)
+            .filter_map(|col| self.cell(Position { col, row }))
+            .map(|cell| cell.grapheme.as_str())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn suspend_count(&self) -> usize {
+        self.suspend_count
+    }
+
+    #[must_use]
+    pub fn cursor_position(&self) -> Position {
+        self.cursor
+    }
+
+    #[must_use]
+    pub fn is_cursor_visible(&self) -> bool {
+        self.is_cursor_visible
+    }
+
+    #[must_use]
+    pub fn cursor_shape(&self) -> CursorShape {
+        self.cursor_shape
+    }
+
+    fn cell_mut(&mut self, position: Position) -> Option<&mut Cell> {
+        if position.col >= self.size.width {
+            return None;
+        }
+        self.cells.get_mut(
+            position
+                .row
+                .saturating_mul(self.size.width)
+                .saturating_add(position.col),
+        )
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn set_up(&mut self) -> Result<(), Error> {
+        self.clear_screen()?;
+        self.move_cursor_to(Position::default())
+    }
+
+    fn terminate(&mut self) -> Result<(), Error> {
+        self.is_cursor_visible = true;
+        self.cursor_shape = CursorShape::Default;
+        self.style = Style::default();
+        Ok(())
+    }
+
+    /// Like a terminal given back to the shell and prepared again, the screen is cleared
+    fn suspend(&mut self) -> Result<(), Error> {
+        self.terminate()?;
+        self.suspend_count = self.suspend_count.saturating_add(1);
+        self.set_up()
+    }
+
+    fn size(&self) -> Result<Size, Error> {
+        Ok(self.size)
+    }
+
+    fn clear_screen(&mut self) -> Result<(), Error> {
+        let style = self.style;
+        for cell in &mut self.cells {
+            *cell = Cell {
+                style,
+                ..Cell::default()
+            };
+        }
+        Ok(())
+    }
+
+    fn move_cursor_to(&mut self, position: Position) -> Result<(), Error> {
+        self.cursor = position;
+        Ok(())
+    }
+
+    fn set_style(&mut self, style: Style) -> Result<(), Error> {
+        self.style = style;
+        Ok(())
+    }
+
+    /// Prints `string` at the cursor. Like in a terminal, the cursor moves after what was printed,
+    /// but text going past the right edge is dropped instead of wrapping.
+    fn print(&mut self, string: &str) -> Result<(), Error> {
+        for grapheme in string.graphemes(true) {
+            let width = grapheme.width().clamp(1, 2);
+            for offset in 0..width {
+                let style = self.style;
+                let position = Position {
+                    col: self.cursor.col.saturating_add(offset),
+                    row: self.cursor.row,
+                };
+                if let Some(cell) = self.cell_mut(position) {
+                    cell.grapheme = if offset == 0 {
+                        grapheme.to_string()
+                    } else {
+                        String::new()
+                    };
+                    cell.style = style;
+                }
+            }
+            self.cursor.col = self.cursor.col.saturating_add(width);
+        }
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> Result<(), Error> {
+        self.is_cursor_visible = false;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> Result<(), Error> {
+        self.is_cursor_visible = true;
+        Ok(())
+    }
+
+    fn set_cursor_shape(&mut self, shape: CursorShape) -> Result<(), Error> {
+        self.cursor_shape = shape;
+        Ok(())
+    }
+
+    fn execute(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn poll_event(&mut self, _timeout: Duration) -> Result<Option<Event>, Error> {
+        Ok(self.events.pop_front())
+    }
+}
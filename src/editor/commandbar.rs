@@ -0,0 +1,78 @@
+use crate::editor::screen::Screen;
+use crate::editor::terminal::{Position, Size};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
+
+/// What a key typed in the command bar did
+pub enum Answer {
+    Typing,
+    Submitted(String),
+    Cancelled,
+}
+
+/// This represents the bar at the bottom of the screen where the answer to a prompt is typed.
+/// It is drawn over the status bar while the prompt is open.
+#[derive(Default)]
+pub struct CommandBar {
+    prompt: String,
+    value: String,
+    needs_redraw: bool,
+    width: usize,
+    position_y: usize,
+}
+
+impl CommandBar {
+    #[must_use]
+    pub fn new(prompt: &str, size: Size) -> Self {
+        let mut command_bar = Self {
+            prompt: prompt.to_string(),
+            ..Self::default()
+        };
+        command_bar.resize(size);
+        command_bar
+    }
+
+    /// The command bar takes the last row of `size`
+    pub fn resize(&mut self, size: Size) {
+        self.width = size.width;
+        self.position_y = size.height.saturating_sub(1);
+        self.needs_redraw = true;
+    }
+
+    /// Edits the value, Enter submits it and Escape or Ctrl+G cancel the prompt
+    pub fn handle_key(&mut self, key: KeyEvent) -> Answer {
+        match (key.code, key.modifiers) {
+            (KeyCode::Enter, _) => return Answer::Submitted(self.value.clone()),
+            (KeyCode::Esc, _) | (KeyCode::Char('g' | 'G'), KeyModifiers::CONTROL) => {
+                return Answer::Cancelled
+            }
+            (KeyCode::Backspace, _) => {
+                self.value.pop();
+            }
+            (KeyCode::Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                self.value.push(character);
+            }
+            _ => return Answer::Typing,
+        }
+        self.needs_redraw = true;
+        Answer::Typing
+    }
+
+    pub fn render(&mut self, screen: &mut Screen) {
+        if !self.needs_redraw || self.width == 0 {
+            return;
+        }
+        screen.print_row(self.position_y, &format!("{}{}", self.prompt, self.value));
+        self.needs_redraw = false;
+    }
+
+    /// The caret is after the typed value
+    #[must_use]
+    pub fn caret_position(&self) -> Position {
+        let col = self.prompt.width().saturating_add(self.value.width());
+        Position {
+            col: col.min(self.width.saturating_sub(1)),
+            row: self.position_y,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+/// How many unchanged lines are shown around the changed ones
+const CONTEXT: usize = 2;
+/// Above this many pairs of lines to compare, the changed lines are shown as all removed then all added
+const MAX_COMPARISONS: usize = 4_000_000;
+
+/// What happened to a line between two texts
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Returns the lines of `old` and `new` in the order of a diff: the lines they have in common once,
+/// with the lines only in `old` before the ones only in `new` between them.
+#[must_use]
+pub fn diff<'a>(old: &'a [String], new: &'a [String]) -> Vec<(Change, &'a str)> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old
+        .iter()
+        .skip(prefix)
+        .rev()
+        .zip(new.iter().skip(prefix).rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_middle = old
+        .get(prefix..old.len().saturating_sub(suffix))
+        .unwrap_or_default();
+    let new_middle = new
+        .get(prefix..new.len().saturating_sub(suffix))
+        .unwrap_or_default();
+    let same = |lines: &'a [String]| lines.iter().map(|line| (Change::Same, line.as_str()));
+    let mut changes: Vec<(Change, &str)> = same(old.get(..prefix).unwrap_or_default()).collect();
+    changes.append(&mut diff_middle(old_middle, new_middle));
+    changes.extend(same(
+        old.get(old.len().saturating_sub(suffix)..)
+            .unwrap_or_default(),
+    ));
+    changes
+}
+
+/// Matches the lines of `old` and `new` with their longest common subsequence
+fn diff_middle<'a>(old: &'a [String], new: &'a [String]) -> Vec<(Change, &'a str)> {
+    let width = new.len().saturating_add(1);
+    let size = old.len().saturating_add(1).saturating_mul(width);
+    // The length of the common subsequence of `old[i..]` and `new[j..]` is at `i * width + j`
+    let mut lengths = vec![0_usize; if size <= MAX_COMPARISONS { size } else { 0 }];
+    let at = |lengths: &[usize], i: usize, j: usize| {
+        lengths
+            .get(i.saturating_mul(width).saturating_add(j))
+            .copied()
+            .unwrap_or_default()
+    };
+    for (i, old_line) in old.iter().enumerate().rev() {
+        for (j, new_line) in new.iter().enumerate().rev() {
+            let length = if old_line == new_line {
+                at(&lengths, i.saturating_add(1), j.saturating_add(1)).saturating_add(1)
+            } else {
+                at(&lengths, i.saturating_add(1), j).max(at(&lengths, i, j.saturating_add(1)))
+            };
+            if let Some(cell) = lengths.get_mut(i.saturating_mul(width).saturating_add(j)) {
+                *cell = length;
+            }
+        }
+    }
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        match (old.get(i), new.get(j)) {
+            (Some(old_line), Some(new_line)) if old_line == new_line && !lengths.is_empty() => {
+                changes.push((Change::Same, old_line.as_str()));
+                i = i.saturating_add(1);
+                j = j.saturating_add(1);
+            }
+            (Some(old_line), new_line)
+                if new_line.is_none()
+                    || at(&lengths, i.saturating_add(1), j)
+                        >= at(&lengths, i, j.saturating_add(1)) =>
+            {
+                changes.push((Change::Removed, old_line.as_str()));
+                i = i.saturating_add(1);
+            }
+            (_, Some(new_line)) => {
+                changes.push((Change::Added, new_line.as_str()));
+                j = j.saturating_add(1);
+            }
+            (_, None) => break,
+        }
+    }
+    changes
+}
+
+/// Writes the difference between `old` and `new` like `diff -u`: the changed lines, starting with `-` or `+`,
+/// with [`CONTEXT`] unchanged lines around them, in hunks starting with the numbers of their lines.
+#[must_use]
+pub fn unified(old: &[String], new: &[String]) -> Vec<String> {
+    let changes = diff(old, new);
+    let is_shown = |index: usize| {
+        let start = index.saturating_sub(CONTEXT);
+        let end = index.saturating_add(CONTEXT).saturating_add(1);
+        changes
+            .get(start..end.min(changes.len()))
+            .is_some_and(|around| around.iter().any(|(change, _)| *change != Change::Same))
+    };
+    let mut lines = Vec::new();
+    let mut hunk: Vec<String> = Vec::new();
+    let (mut old_number, mut new_number) = (1_usize, 1_usize);
+    let (mut old_start, mut new_start, mut old_count, mut new_count) = (0, 0, 0_usize, 0_usize);
+    for (index, (change, line)) in changes.iter().enumerate() {
+        if is_shown(index) {
+            if hunk.is_empty() {
+                (old_start, new_start, old_count, new_count) = (old_number, new_number, 0, 0);
+            }
+            let sign = match change {
+                Change::Same => ' ',
+                Change::Removed => '-',
+                Change::Added => '+',
+            };
+            hunk.push(format!("{sign}{line}"));
+            if *change != Change::Added {
+                old_count = old_count.saturating_add(1);
+            }
+            if *change != Change::Removed {
+                new_count = new_count.saturating_add(1);
+            }
+        } else if !hunk.is_empty() {
+            lines.push(format!(
+                "@@ -{old_start},{old_count} +{new_start},{new_count} @@"
+            ));
+            lines.append(&mut hunk);
+        }
+        if *change != Change::Added {
+            old_number = old_number.saturating_add(1);
+        }
+        if *change != Change::Removed {
+            new_number = new_number.saturating_add(1);
+        }
+    }
+    if !hunk.is_empty() {
+        lines.push(format!(
+            "@@ -{old_start},{old_count} +{new_start},{new_count} @@"
+        ));
+        lines.append(&mut hunk);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split(' ').map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = lines("a b c d e f g h i");
+        let new = lines("a b x d e f g i j");
+        assert_eq!(
+            unified(&old, &new),
+            vec![
+                "@@ -1,9 +1,9 @@",
+                " a",
+                " b",
+                "-c",
+                "+x",
+                " d",
+                " e",
+                " f",
+                " g",
+                "-h",
+                " i",
+                "+j"
+            ]
+        );
+        let old = lines("1 2 3 4 5 6 7 8 9");
+        let new = lines("1 2 3 4 5 6 7 8 0");
+        assert_eq!(
+            unified(&old, &new),
+            vec!["@@ -7,3 +7,3 @@", " 7", " 8", "-9", "+0"]
+        );
+        let (old, new) = (lines("a b c"), lines("b c a"));
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                (Change::Removed, "a"),
+                (Change::Same, "b"),
+                (Change::Same, "c"),
+                (Change::Added, "a")
+            ]
+        );
+        assert!(unified(&new, &new).is_empty());
+    }
+}
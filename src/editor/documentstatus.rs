@@ -0,0 +1,78 @@
+/// This represents what the status bar needs to know about the document being edited
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct DocumentStatus {
+    pub total_lines: usize,
+    pub current_line_index: usize,
+    pub file_name: Option<String>,
+    pub load_progress: Option<u8>,
+    pub is_read_only: bool,
+    /// If the file is followed: `true` while the view scrolls with it, `false` once the user scrolled away
+    pub following: Option<bool>,
+    /// How many lines are shown, if only the lines matching a pattern are
+    pub shown_lines: Option<usize>,
+    /// The offset of the caret and the size of the file, if its bytes are shown in hex
+    pub hex_offset: Option<(usize, usize)>,
+    /// The mode of the modal editing layer, if it is enabled
+    pub mode: Option<String>,
+    /// The number of the buffer and how many are open, if there are several
+    pub buffer: Option<(usize, usize)>,
+}
+
+impl DocumentStatus {
+    #[must_use]
+    pub fn mode_to_string(&self) -> String {
+        self.mode
+            .as_ref()
+            .map_or(String::new(), |mode| format!("-- {mode} -- "))
+    }
+
+    #[must_use]
+    pub fn line_count_to_string(&self) -> String {
+        match self.hex_offset {
+            Some((_, size)) => format!("{size} bytes"),
+            None => format!("{} lines", self.total_lines),
+        }
+    }
+
+    #[must_use]
+    pub fn position_indicator_to_string(&self) -> String {
+        if let Some((offset, size)) = self.hex_offset {
+            return format!("{offset:#x}/{size:#x}");
+        }
+        format!(
+            "{}/{}",
+            self.current_line_index
+                .saturating_add(1)
+                .min(self.total_lines),
+            self.total_lines
+        )
+    }
+
+    #[must_use]
+    pub fn file_name_to_string(&self) -> String {
+        self.file_name
+            .clone()
+            .unwrap_or_else(|| "[No Name]".to_string())
+    }
+
+    #[must_use]
+    pub fn flags_to_string(&self) -> String {
+        let loading = self
+            .load_progress
+            .map_or(String::new(), |progress| format!(" (loading {progress}%)"));
+        // A followed file is always read-only
+        let read_only = match self.following {
+            Some(true) => " [following]",
+            Some(false) => " [paused]",
+            None if self.is_read_only => " [read-only]",
+            None => "",
+        };
+        let filtered = self
+            .shown_lines
+            .map_or(String::new(), |count| format!(" [filtered: {count}]"));
+        let buffer = self.buffer.map_or(String::new(), |(number, count)| {
+            format!(" [{number}/{count}]")
+        });
+        format!("{loading}{read_only}{filtered}{buffer}")
+    }
+}
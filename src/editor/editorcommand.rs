@@ -1,7 +1,15 @@
-use crate::editor::editorcommand::Direction::{Down, End, Home, Left, PageDown, PageUp, Right, Up};
-use crate::editor::terminal::Size;
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crate::editor::editorcommand::Direction::{
+    Down, End, Home, Left, PageDown, PageUp, Right, Up, WordLeft, WordRight,
+};
+use crate::editor::macros::DEFAULT_SLOT;
+use crate::editor::terminal::{Position, Size};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use std::fmt;
+use std::str::FromStr;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Direction {
     PageUp,
     PageDown,
@@ -11,8 +19,77 @@ pub enum Direction {
     Left,
     Right,
     Down,
+    /// To the start of the previous word
+    WordLeft,
+    /// To the end of the next word
+    WordRight,
+    /// To the start of the next word
+    NextWordStart,
+    /// To the start of the first line
+    FileStart,
+    /// To the start of the last line
+    FileEnd,
 }
 
+/// The line to go to, line numbers starting at 1
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineTarget {
+    Number(usize),
+    /// Lines down from the current one, or up if negative
+    Relative(isize),
+    /// A percentage of the file, 100% being the last line
+    Percent(usize),
+}
+
+/// Where to move the caret, written `line`, `line:column`, `+N`, `-N` or `N%`, columns starting at 1
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GoToTarget {
+    pub line: LineTarget,
+    pub column: Option<usize>,
+}
+
+impl FromStr for GoToTarget {
+    type Err = String;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let (line, column) = match text.split_once(':') {
+            Some((line, column)) => (line, Some(column)),
+            None => (text, None),
+        };
+        let line = if let Some(percent) = line.strip_suffix('%') {
+            percent.parse().map(LineTarget::Percent)
+        } else if line.starts_with(['+', '-']) {
+            line.parse().map(LineTarget::Relative)
+        } else {
+            line.parse().map(LineTarget::Number)
+        }
+        .map_err(|_| format!("Invalid line {line}"))?;
+        let column = column
+            .map(|column| {
+                column
+                    .parse()
+                    .map_err(|_| format!("Invalid column {column}"))
+            })
+            .transpose()?;
+        Ok(Self { line, column })
+    }
+}
+
+impl fmt::Display for GoToTarget {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            LineTarget::Number(number) => write!(formatter, "{number}")?,
+            LineTarget::Relative(delta) => write!(formatter, "{delta:+}")?,
+            LineTarget::Percent(percent) => write!(formatter, "{percent}%")?,
+        }
+        if let Some(column) = self.column {
+            write!(formatter, ":{column}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum EditorCommand {
     Move(Direction),
     Resize(Size),
@@ -20,7 +97,182 @@ pub enum EditorCommand {
     Enter,
     Delete,
     Backspace,
+    DeleteWordBackward,
+    DeleteWordForward,
+    /// Moves the caret to the bracket matching the one under or before it
+    JumpToMatchingBracket,
     Quit,
+    /// Moves the caret, extending the selection from where it was
+    Select(Direction),
+    /// Like [`EditorCommand::Select`], the caret staying on its line
+    SelectInLine(Direction),
+    /// Starts a selection at the caret
+    StartSelection,
+    /// Starts a selection of whole lines at the caret, it grows by whole lines as the caret moves
+    StartLineSelection,
+    ClearSelection,
+    /// Clears the selection and puts the caret at its start
+    CollapseSelection,
+    Copy,
+    Cut,
+    /// Inserts the copied text at the caret
+    Paste,
+    /// Inserts the copied text after the grapheme under the caret, or below the current line for whole lines
+    PasteAfter,
+    OpenLineBelow,
+    OpenLineAbove,
+    /// Cuts from the caret to the end of the line, or the line break at the end of the line.
+    /// Kills following each other are gathered in one entry of the kill ring.
+    KillLine,
+    /// Right after a paste, replaces the pasted text by the entry of the kill ring before it
+    YankPop,
+    /// Starts recording the commands that follow into a macro slot
+    StartRecording(char),
+    StopRecording,
+    /// Shows the next open file
+    NextBuffer,
+    PreviousBuffer,
+    /// Asks for the line to go to
+    PromptGoToLine,
+    /// Writes the text to its file, asking for a file name if it has none
+    Save,
+    /// Gives the terminal back to the shell until the editor is resumed, like Ctrl-Z in a shell
+    Suspend,
+    /// Refuses or allows edits in the view
+    ToggleReadOnly,
+    /// Starts or stops showing what is written at the end of the file, like `tail -f`
+    ToggleFollow,
+    /// Asks for the pattern of the lines to show, or shows all the lines again if the view is filtered
+    PromptFilter,
+    /// Shows the bytes of the file in hex, to overwrite them, or its text again
+    ToggleHex,
+    /// Moves the caret and centers its line in the view
+    GoTo(GoToTarget),
+    /// Plays the macro of a slot `times` times, or the last one recorded or played if there is no slot.
+    /// Without a slot, this ends the recording instead if there is one.
+    PlayMacro {
+        slot: Option<char>,
+        times: usize,
+    },
+    /// Moves a corner of the rectangular selection, which starts at the caret
+    SelectBlock(Direction),
+    /// Extends the rectangular selection to a screen position
+    DragBlock(Position),
+    /// Adds a caret at a screen position, or removes the one already there
+    ToggleCaret(Position),
+    /// Adds a caret on the line above the first caret
+    AddCaretAbove,
+    /// Adds a caret on the line below the last caret
+    AddCaretBelow,
+    /// Selects the word at the caret, or adds a caret selecting the next occurrence of the selected text
+    AddNextOccurrence,
+    /// Positions are screen positions, the view translates them to locations in the text
+    Click(Position),
+    DoubleClick(Position),
+    Drag(Position),
+    /// Moves the view, not the caret
+    Scroll(Direction),
+}
+
+impl EditorCommand {
+    /// The commands that change the text, which read-only views refuse
+    #[must_use]
+    pub fn edits_text(&self) -> bool {
+        matches!(
+            self,
+            Self::Insert(_)
+                | Self::Enter
+                | Self::Delete
+                | Self::Backspace
+                | Self::DeleteWordBackward
+                | Self::DeleteWordForward
+                | Self::Cut
+                | Self::Paste
+                | Self::PasteAfter
+                | Self::KillLine
+                | Self::YankPop
+                | Self::OpenLineBelow
+                | Self::OpenLineAbove
+        )
+    }
+
+    /// The default key bindings
+    fn from_key(code: KeyCode, modifiers: KeyModifiers) -> Result<Self, String> {
+        match (code, modifiers) {
+            (KeyCode::Char('q' | 'Q'), KeyModifiers::CONTROL) => Ok(Self::Quit),
+            (KeyCode::Char('b' | 'B'), KeyModifiers::CONTROL) => Ok(Self::JumpToMatchingBracket),
+            (KeyCode::Char('c' | 'C'), KeyModifiers::CONTROL) => Ok(Self::Copy),
+            (KeyCode::Char('x' | 'X'), KeyModifiers::CONTROL) => Ok(Self::Cut),
+            (KeyCode::Char('v' | 'V'), KeyModifiers::CONTROL) => Ok(Self::Paste),
+            (KeyCode::Char('g' | 'G'), KeyModifiers::CONTROL) => Ok(Self::PromptGoToLine),
+            (KeyCode::Char('s' | 'S'), KeyModifiers::CONTROL) => Ok(Self::Save),
+            (KeyCode::Char('z' | 'Z'), KeyModifiers::CONTROL) => Ok(Self::Suspend),
+            (KeyCode::Char('d' | 'D'), KeyModifiers::CONTROL) => Ok(Self::AddNextOccurrence),
+            (KeyCode::Esc, KeyModifiers::NONE) => Ok(Self::ClearSelection),
+            (KeyCode::F(3), KeyModifiers::NONE) => Ok(Self::StartRecording(DEFAULT_SLOT)),
+            (KeyCode::F(4), KeyModifiers::NONE) => Ok(Self::PlayMacro {
+                slot: None,
+                times: 1,
+            }),
+            (KeyCode::Tab, KeyModifiers::NONE) => Ok(Self::Insert('\t')),
+            (KeyCode::Char(char), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                Ok(Self::Insert(char))
+            }
+            (KeyCode::Enter, KeyModifiers::NONE) => Ok(Self::Enter),
+            (KeyCode::Backspace, KeyModifiers::NONE) => Ok(Self::Backspace),
+            (KeyCode::Delete, KeyModifiers::NONE) => Ok(Self::Delete),
+            // Most terminals send Ctrl+H for Ctrl+Backspace
+            (KeyCode::Backspace | KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                Ok(Self::DeleteWordBackward)
+            }
+            (KeyCode::Delete, KeyModifiers::CONTROL) => Ok(Self::DeleteWordForward),
+            (KeyCode::Left, modifiers)
+                if modifiers == KeyModifiers::CONTROL | KeyModifiers::SHIFT =>
+            {
+                Ok(Self::Select(WordLeft))
+            }
+            (KeyCode::Right, modifiers)
+                if modifiers == KeyModifiers::CONTROL | KeyModifiers::SHIFT =>
+            {
+                Ok(Self::Select(WordRight))
+            }
+            (KeyCode::Up, KeyModifiers::SHIFT) => Ok(Self::Select(Up)),
+            (KeyCode::Down, KeyModifiers::SHIFT) => Ok(Self::Select(Down)),
+            (KeyCode::Left, KeyModifiers::SHIFT) => Ok(Self::Select(Left)),
+            (KeyCode::Right, KeyModifiers::SHIFT) => Ok(Self::Select(Right)),
+            (KeyCode::Home, KeyModifiers::SHIFT) => Ok(Self::Select(Home)),
+            (KeyCode::End, KeyModifiers::SHIFT) => Ok(Self::Select(End)),
+            (KeyCode::Left, KeyModifiers::CONTROL) => Ok(Self::Move(WordLeft)),
+            (KeyCode::Right, KeyModifiers::CONTROL) => Ok(Self::Move(WordRight)),
+            (KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right, modifiers)
+                if modifiers == KeyModifiers::ALT | KeyModifiers::SHIFT =>
+            {
+                Ok(Self::SelectBlock(match code {
+                    KeyCode::Up => Up,
+                    KeyCode::Down => Down,
+                    KeyCode::Left => Left,
+                    _ => Right,
+                }))
+            }
+            (KeyCode::Char('r' | 'R'), KeyModifiers::ALT) => Ok(Self::ToggleReadOnly),
+            (KeyCode::Char('f' | 'F'), KeyModifiers::ALT) => Ok(Self::ToggleFollow),
+            (KeyCode::Char('l' | 'L'), KeyModifiers::ALT) => Ok(Self::PromptFilter),
+            (KeyCode::Char('x' | 'X'), KeyModifiers::ALT) => Ok(Self::ToggleHex),
+            (KeyCode::Up, KeyModifiers::ALT) => Ok(Self::AddCaretAbove),
+            (KeyCode::Down, KeyModifiers::ALT) => Ok(Self::AddCaretBelow),
+            (KeyCode::Up, _) => Ok(Self::Move(Up)),
+            (KeyCode::Down, _) => Ok(Self::Move(Down)),
+            (KeyCode::Left, _) => Ok(Self::Move(Left)),
+            (KeyCode::Right, _) => Ok(Self::Move(Right)),
+            (KeyCode::PageDown, KeyModifiers::CONTROL) => Ok(Self::NextBuffer),
+            (KeyCode::PageUp, KeyModifiers::CONTROL) => Ok(Self::PreviousBuffer),
+            (KeyCode::PageDown, _) => Ok(Self::Move(PageDown)),
+            (KeyCode::PageUp, _) => Ok(Self::Move(PageUp)),
+            (KeyCode::Home, _) => Ok(Self::Move(Home)),
+            (KeyCode::End, _) => Ok(Self::Move(End)),
+            _ => Err(format!("Key code not supported {code:?}")),
+        }
+    }
 }
 
 impl TryFrom<Event> for EditorCommand {
@@ -29,30 +281,68 @@ impl TryFrom<Event> for EditorCommand {
         match event {
             Event::Key(KeyEvent {
                 code, modifiers, ..
-            }) => match (code, modifiers) {
-                (KeyCode::Char('q' | 'Q'), KeyModifiers::CONTROL) => Ok(Self::Quit),
-                (KeyCode::Tab, KeyModifiers::NONE) => Ok(Self::Insert('\t')),
-                (KeyCode::Char(char), KeyModifiers::NONE) => Ok(Self::Insert(char)),
-                (KeyCode::Enter, KeyModifiers::NONE) => Ok(Self::Enter),
-                (KeyCode::Backspace, KeyModifiers::NONE) => Ok(Self::Backspace),
-                (KeyCode::Delete, KeyModifiers::NONE) => Ok(Self::Delete),
-                (KeyCode::Up, _) => Ok(Self::Move(Up)),
-                (KeyCode::Down, _) => Ok(Self::Move(Down)),
-                (KeyCode::Left, _) => Ok(Self::Move(Left)),
-                (KeyCode::Right, _) => Ok(Self::Move(Right)),
-                (KeyCode::PageDown, _) => Ok(Self::Move(PageDown)),
-                (KeyCode::PageUp, _) => Ok(Self::Move(PageUp)),
-                (KeyCode::Home, _) => Ok(Self::Move(Home)),
-                (KeyCode::End, _) => Ok(Self::Move(End)),
-                _ => Err(format!("Key code not supported {code:?}")),
-            },
+            }) => Self::from_key(code, modifiers),
             //Systems where usize < u16 will cause problems
             #[allow(clippy::as_conversions)]
             Event::Resize(width_u16, height_u16) => Ok(Self::Resize(Size {
                 width: width_u16 as usize,
                 height: height_u16 as usize,
             })),
+            //Systems where usize < u16 will cause problems
+            #[allow(clippy::as_conversions)]
+            Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers,
+            }) => {
+                let position = Position {
+                    col: column as usize,
+                    row: row as usize,
+                };
+                match kind {
+                    MouseEventKind::Down(MouseButton::Left)
+                        if modifiers == KeyModifiers::CONTROL =>
+                    {
+                        Ok(Self::ToggleCaret(position))
+                    }
+                    MouseEventKind::Down(MouseButton::Left) => Ok(Self::Click(position)),
+                    MouseEventKind::Drag(MouseButton::Left) if modifiers == KeyModifiers::ALT => {
+                        Ok(Self::DragBlock(position))
+                    }
+                    MouseEventKind::Drag(MouseButton::Left) => Ok(Self::Drag(position)),
+                    MouseEventKind::ScrollUp => Ok(Self::Scroll(Up)),
+                    MouseEventKind::ScrollDown => Ok(Self::Scroll(Down)),
+                    MouseEventKind::ScrollLeft => Ok(Self::Scroll(Left)),
+                    MouseEventKind::ScrollRight => Ok(Self::Scroll(Right)),
+                    _ => Err(format!("Mouse event not supported {kind:?}")),
+                }
+            }
             _ => Err(format!("Event not supported {event:?}")),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_go_to_target() {
+        let target = |line, column| GoToTarget { line, column };
+        assert_eq!("42".parse(), Ok(target(LineTarget::Number(42), None)));
+        assert_eq!(
+            " 42:7 ".parse(),
+            Ok(target(LineTarget::Number(42), Some(7)))
+        );
+        assert_eq!("+3".parse(), Ok(target(LineTarget::Relative(3), None)));
+        assert_eq!("-3".parse(), Ok(target(LineTarget::Relative(-3), None)));
+        assert_eq!("50%".parse(), Ok(target(LineTarget::Percent(50), None)));
+        assert!("".parse::<GoToTarget>().is_err());
+        assert!("12:x".parse::<GoToTarget>().is_err());
+        assert!("-3%".parse::<GoToTarget>().is_err());
+        for text in ["42:7", "+3", "-3", "50%"] {
+            assert_eq!(text.parse::<GoToTarget>().unwrap().to_string(), text);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::editor::editorcommand::Direction::{
+    Down, End, FileEnd, FileStart, Home, Left, PageDown, PageUp, Right, Up, WordLeft, WordRight,
+};
+use crate::editor::editorcommand::EditorCommand;
+use crate::editor::keymap::{Keymap, Lookup};
+use crate::editor::macros::DEFAULT_SLOT;
+use crossterm::event::{Event, KeyEvent};
+
+/// This is the emacs binding profile: it turns keys, and sequences of keys like `C-x C-c`, into [`EditorCommand`]s.
+/// Keys it does not bind are turned into commands like without a profile, so typing text works the same.
+pub struct Emacs {
+    keymap: Keymap,
+    /// `C-SPC` was typed, motions extend the selection from there until a command uses it
+    is_mark_set: bool,
+}
+
+impl Default for Emacs {
+    fn default() -> Self {
+        let mut keymap = Keymap::default();
+        for (notation, direction) in [
+            ("C-a", Home),
+            ("C-e", End),
+            ("C-f", Right),
+            ("C-b", Left),
+            ("C-n", Down),
+            ("C-p", Up),
+            ("M-f", WordRight),
+            ("M-b", WordLeft),
+            ("C-v", PageDown),
+            ("M-v", PageUp),
+            ("M-<", FileStart),
+        ] {
+            keymap.bind(notation, vec![EditorCommand::Move(direction)]);
+        }
+        keymap.bind(
+            "M->",
+            vec![EditorCommand::Move(FileEnd), EditorCommand::Move(End)],
+        );
+        keymap.bind("C-d", vec![EditorCommand::Delete]);
+        keymap.bind("M-d", vec![EditorCommand::DeleteWordForward]);
+        keymap.bind("M-DEL", vec![EditorCommand::DeleteWordBackward]);
+        keymap.bind("C-k", vec![EditorCommand::KillLine]);
+        keymap.bind("C-w", vec![EditorCommand::Cut]);
+        keymap.bind(
+            "M-w",
+            vec![EditorCommand::Copy, EditorCommand::ClearSelection],
+        );
+        keymap.bind("C-y", vec![EditorCommand::Paste]);
+        keymap.bind("M-y", vec![EditorCommand::YankPop]);
+        keymap.bind("C-SPC", vec![EditorCommand::StartSelection]);
+        keymap.bind("C-g", vec![EditorCommand::ClearSelection]);
+        keymap.bind("C-o", vec![EditorCommand::Enter, EditorCommand::Move(Left)]);
+        keymap.bind("C-x C-c", vec![EditorCommand::Quit]);
+        keymap.bind("C-x C-s", vec![EditorCommand::Save]);
+        keymap.bind("C-x C-q", vec![EditorCommand::ToggleReadOnly]);
+        keymap.bind("C-c f", vec![EditorCommand::ToggleFollow]);
+        keymap.bind("M-s o", vec![EditorCommand::PromptFilter]);
+        keymap.bind("C-c x", vec![EditorCommand::ToggleHex]);
+        keymap.bind("C-x <right>", vec![EditorCommand::NextBuffer]);
+        keymap.bind("C-x <left>", vec![EditorCommand::PreviousBuffer]);
+        keymap.bind("M-g g", vec![EditorCommand::PromptGoToLine]);
+        keymap.bind("M-g M-g", vec![EditorCommand::PromptGoToLine]);
+        keymap.bind("C-x (", vec![EditorCommand::StartRecording(DEFAULT_SLOT)]);
+        keymap.bind("C-x )", vec![EditorCommand::StopRecording]);
+        keymap.bind(
+            "C-x e",
+            vec![EditorCommand::PlayMacro {
+                slot: None,
+                times: 1,
+            }],
+        );
+        keymap.bind(
+            "C-x h",
+            vec![
+                EditorCommand::Move(FileStart),
+                EditorCommand::StartSelection,
+                EditorCommand::Move(FileEnd),
+                EditorCommand::Move(End),
+            ],
+        );
+        Self {
+            keymap,
+            is_mark_set: false,
+        }
+    }
+}
+
+impl Emacs {
+    /// Returns the prefix typed so far, like `C-x`, or `None` if no sequence is being typed
+    #[must_use]
+    pub fn pending_keys(&self) -> Option<String> {
+        let pending = self.keymap.pending();
+        if pending.is_empty() {
+            return None;
+        }
+        let keys: Vec<String> = pending.iter().map(ToString::to_string).collect();
+        Some(keys.join(" "))
+    }
+
+    /// Returns the commands to run for `key`, which can be none while a sequence is being typed
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<EditorCommand> {
+        let commands = match self.keymap.handle_key(key) {
+            Lookup::Commands(commands) => commands,
+            Lookup::Pending | Lookup::Undefined(_) => Vec::new(),
+            Lookup::Unbound => EditorCommand::try_from(Event::Key(key))
+                .map(|command| vec![command])
+                .unwrap_or_default(),
+        };
+        commands
+            .into_iter()
+            .map(|command| self.apply_mark(command))
+            .collect()
+    }
+
+    /// While the mark is set, motions select the text they go over
+    fn apply_mark(&mut self, command: EditorCommand) -> EditorCommand {
+        match command {
+            EditorCommand::StartSelection => self.is_mark_set = true,
+            EditorCommand::Move(direction) if self.is_mark_set => {
+                return EditorCommand::Select(direction);
+            }
+            EditorCommand::Move(_) | EditorCommand::Select(_) | EditorCommand::Scroll(_) => {}
+            _ => self.is_mark_set = false,
+        }
+        command
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::backend::memory::MemoryBackend;
+    use crate::editor::keymap::Key;
+    use crate::editor::test::row;
+    use crate::editor::Editor;
+
+    fn set_up() -> Editor<MemoryBackend> {
+        crate::editor::test::set_up(40, &["--emacs", "./text-test/test.txt"])
+    }
+
+    /// Types the keys written in `notation`, see [`Key::parse_sequence`]
+    fn type_keys(editor: &mut Editor<MemoryBackend>, notation: &str) {
+        for key in Key::parse_sequence(notation).unwrap() {
+            editor.evaluate_event(Event::Key(KeyEvent::new(key.code, key.modifiers)));
+        }
+        editor.refresh_screen();
+    }
+
+    #[test]
+    fn test_motions_and_typing() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "C-n C-e C-b ! C-a M-f M-f -");
+        assert_eq!(row(&editor, 1), "rust is- 𝒻𝒶𝓃𝒸𝓎!!");
+        type_keys(&mut editor, "C-p C-d");
+        assert_eq!(row(&editor, 0), "rust is reat!");
+    }
+
+    #[test]
+    fn test_kill_and_yank() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "M-f C-k C-k C-k");
+        assert_eq!(row(&editor, 0), "rust");
+        assert_eq!(row(&editor, 1), "rust is ѕ¢αяу!");
+        type_keys(&mut editor, "C-e C-y");
+        assert_eq!(row(&editor, 0), "rust is great!");
+        assert_eq!(row(&editor, 1), "rust is 𝒻𝒶𝓃𝒸𝓎!");
+        type_keys(&mut editor, "C-a C-k");
+        assert_eq!(row(&editor, 1), "");
+        type_keys(&mut editor, "C-y C-y");
+        assert_eq!(row(&editor, 1), "rust is 𝒻𝒶𝓃𝒸𝓎!rust is 𝒻𝒶𝓃𝒸𝓎!");
+        type_keys(&mut editor, "M-y");
+        assert_eq!(row(&editor, 1), "rust is 𝒻𝒶𝓃𝒸𝓎! is great!");
+        assert_eq!(row(&editor, 2), "rust is 𝒻𝒶𝓃𝒸𝓎!");
+        type_keys(&mut editor, "M-y");
+        assert_eq!(row(&editor, 1), "rust is 𝒻𝒶𝓃𝒸𝓎!rust is 𝒻𝒶𝓃𝒸𝓎!");
+        type_keys(&mut editor, "a M-y");
+        assert_eq!(row(&editor, 1), "rust is 𝒻𝒶𝓃𝒸𝓎!rust is 𝒻𝒶𝓃𝒸𝓎!a");
+    }
+
+    #[test]
+    fn test_mark_and_region() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "C-SPC M-f M-f C-w");
+        assert_eq!(row(&editor, 0), " great!");
+        type_keys(&mut editor, "C-e C-y");
+        assert_eq!(row(&editor, 0), " great!rust is");
+        type_keys(&mut editor, "C-a C-SPC C-f C-f M-w C-e C-y");
+        assert_eq!(row(&editor, 0), " great!rust is g");
+        type_keys(&mut editor, "C-a C-SPC C-g C-f C-d");
+        assert_eq!(row(&editor, 0), " reat!rust is g");
+    }
+
+    #[test]
+    fn test_prefix_sequences() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "C-x");
+        assert!(row(&editor, 9).starts_with("-- C-x -- "));
+        type_keys(&mut editor, "z");
+        assert_eq!(row(&editor, 0), "rust is great!");
+        type_keys(&mut editor, "C-x h DEL");
+        assert_eq!(row(&editor, 0), "");
+        assert_eq!(row(&editor, 1), "~");
+        type_keys(&mut editor, "C-x C-c");
+        assert!(row(&editor, 9).starts_with("Modified: ./text-test/test.txt."));
+        type_keys(&mut editor, "d RET");
+        assert!(editor.should_quit());
+    }
+
+    #[test]
+    fn test_macros() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "C-x ( C-a > C-n C-x )");
+        assert_eq!(row(&editor, 0), ">rust is great!");
+        type_keys(&mut editor, "C-x e C-x e");
+        assert_eq!(row(&editor, 1), ">rust is 𝒻𝒶𝓃𝒸𝓎!");
+        assert_eq!(row(&editor, 2), ">rust is ѕ¢αяу!");
+    }
+}
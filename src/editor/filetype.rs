@@ -0,0 +1,55 @@
+use std::path::Path;
+
+/// What is inserted for one level of indentation
+pub const INDENT: &str = "    ";
+
+/// This represents the language of a file, used for language rules like indentation
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileType {
+    Rust,
+    Python,
+    #[default]
+    Text,
+}
+
+impl FileType {
+    #[must_use]
+    pub fn from_file_name(file_name: &str) -> Self {
+        match Path::new(file_name)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("rs") => Self::Rust,
+            Some("py") => Self::Python,
+            _ => Self::Text,
+        }
+    }
+
+    /// Returns `true` if a new line after `text` should be indented one more level
+    #[must_use]
+    pub fn opens_block(self, text: &str) -> bool {
+        let text = text.trim_end();
+        match self {
+            Self::Rust => text.ends_with(['{', '(', '[']),
+            Self::Python => text.ends_with(':'),
+            Self::Text => false,
+        }
+    }
+
+    /// Returns the bracket closing `opening`, if `opening` opens a block in this language
+    #[must_use]
+    pub fn closing_bracket(self, opening: char) -> Option<char> {
+        match (self, opening) {
+            (Self::Rust, '{') => Some('}'),
+            (Self::Rust, '(') => Some(')'),
+            (Self::Rust, '[') => Some(']'),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if typing `character` at the start of a line should remove one level of indentation
+    #[must_use]
+    pub fn is_closing_bracket(self, character: char) -> bool {
+        matches!((self, character), (Self::Rust, '}' | ')' | ']'))
+    }
+}
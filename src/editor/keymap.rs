@@ -0,0 +1,226 @@
+use crate::editor::editorcommand::EditorCommand;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The keys that are written with a name instead of a character
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("SPC", KeyCode::Char(' ')),
+    ("RET", KeyCode::Enter),
+    ("TAB", KeyCode::Tab),
+    ("DEL", KeyCode::Backspace),
+    ("ESC", KeyCode::Esc),
+    ("<left>", KeyCode::Left),
+    ("<right>", KeyCode::Right),
+    ("<up>", KeyCode::Up),
+    ("<down>", KeyCode::Down),
+    ("<home>", KeyCode::Home),
+    ("<end>", KeyCode::End),
+    ("<prior>", KeyCode::PageUp),
+    ("<next>", KeyCode::PageDown),
+];
+
+/// A key press, without the details of the event that are not used to find bindings
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        let modifiers = match event.code {
+            // The shift is already in the character, `M->` is typed with shift but written without it
+            KeyCode::Char(_) => event.modifiers.difference(KeyModifiers::SHIFT),
+            _ => event.modifiers,
+        };
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl Key {
+    /// Parses a sequence of keys written like in emacs, for example `C-x C-c` or `M-<`.
+    /// Besides characters, keys can be one of the [`NAMED_KEYS`], like `SPC` or `<left>`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a key can not be parsed
+    pub fn parse_sequence(notation: &str) -> Result<Vec<Self>, String> {
+        notation.split_whitespace().map(Self::parse).collect()
+    }
+
+    fn parse(notation: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = notation;
+        loop {
+            let modifier = match rest.get(..2) {
+                Some("C-") => KeyModifiers::CONTROL,
+                Some("M-") => KeyModifiers::ALT,
+                Some("S-") => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            // A single character is a key, even `-`
+            if rest.len() == 2 {
+                break;
+            }
+            modifiers |= modifier;
+            rest = rest.get(2..).unwrap_or_default();
+        }
+        let named = NAMED_KEYS.iter().find(|(name, _)| *name == rest);
+        let mut characters = rest.chars();
+        let code = match (named, characters.next(), characters.next()) {
+            (Some((_, code)), _, _) => *code,
+            (None, Some(character), None) => KeyCode::Char(character),
+            _ => return Err(format!("Unknown key {notation}")),
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, prefix) in [
+            (KeyModifiers::CONTROL, "C-"),
+            (KeyModifiers::ALT, "M-"),
+            (KeyModifiers::SHIFT, "S-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(formatter, "{prefix}")?;
+            }
+        }
+        match (
+            NAMED_KEYS.iter().find(|(_, code)| *code == self.code),
+            self.code,
+        ) {
+            (Some((name, _)), _) => write!(formatter, "{name}"),
+            (None, KeyCode::Char(character)) => write!(formatter, "{character}"),
+            (None, code) => write!(formatter, "{code}"),
+        }
+    }
+}
+
+/// What the keys typed so far mean
+pub enum Lookup {
+    Commands(Vec<EditorCommand>),
+    /// The keys are the start of at least one sequence, more keys are needed
+    Pending,
+    /// A sequence that started like a binding went on with a key that is not part of it
+    Undefined(Vec<Key>),
+    /// The key is not bound and was not preceded by a prefix
+    Unbound,
+}
+
+/// This binds sequences of keys, like `C-x C-c`, to the commands they run.
+/// Keys are fed one by one to [`Keymap::handle_key`], which remembers the prefix typed so far.
+#[derive(Default)]
+pub struct Keymap {
+    bindings: HashMap<Vec<Key>, Vec<EditorCommand>>,
+    pending: Vec<Key>,
+}
+
+impl Keymap {
+    /// Binds `notation`, parsed with [`Key::parse_sequence`], to `commands`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `notation` can not be parsed, bindings are written in the code
+    pub fn bind(&mut self, notation: &str, commands: Vec<EditorCommand>) {
+        let keys = Key::parse_sequence(notation).unwrap_or_else(|error| panic!("{error}"));
+        self.bindings.insert(keys, commands);
+    }
+
+    /// Returns the keys of the sequence being typed
+    #[must_use]
+    pub fn pending(&self) -> &[Key] {
+        &self.pending
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Lookup {
+        self.pending.push(Key::from(key));
+        if let Some(commands) = self.bindings.get(&self.pending) {
+            self.pending.clear();
+            return Lookup::Commands(commands.clone());
+        }
+        let is_prefix = self
+            .bindings
+            .keys()
+            .any(|keys| keys.starts_with(&self.pending));
+        if is_prefix {
+            return Lookup::Pending;
+        }
+        let keys = std::mem::take(&mut self.pending);
+        if keys.len() > 1 {
+            Lookup::Undefined(keys)
+        } else {
+            Lookup::Unbound
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn press(keymap: &mut Keymap, notation: &str) -> Vec<Lookup> {
+        Key::parse_sequence(notation)
+            .unwrap()
+            .into_iter()
+            .map(|key| keymap.handle_key(KeyEvent::new(key.code, key.modifiers)))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_sequence() {
+        assert_eq!(
+            Key::parse_sequence("C-x M-< C-M-SPC -").unwrap(),
+            vec![
+                Key {
+                    code: KeyCode::Char('x'),
+                    modifiers: KeyModifiers::CONTROL
+                },
+                Key {
+                    code: KeyCode::Char('<'),
+                    modifiers: KeyModifiers::ALT
+                },
+                Key {
+                    code: KeyCode::Char(' '),
+                    modifiers: KeyModifiers::CONTROL | KeyModifiers::ALT
+                },
+                Key {
+                    code: KeyCode::Char('-'),
+                    modifiers: KeyModifiers::NONE
+                },
+            ]
+        );
+        assert!(Key::parse_sequence("C-xy").is_err());
+        let keys: Vec<String> = Key::parse_sequence("C-x M-SPC <left>")
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(keys, vec!["C-x", "M-SPC", "<left>"]);
+    }
+
+    #[test]
+    fn test_sequences() {
+        let mut keymap = Keymap::default();
+        keymap.bind("C-x C-c", vec![EditorCommand::Quit]);
+        keymap.bind("C-k", vec![EditorCommand::KillLine]);
+        assert!(matches!(
+            press(&mut keymap, "C-x C-c").as_slice(),
+            [Lookup::Pending, Lookup::Commands(_)]
+        ));
+        assert!(matches!(
+            press(&mut keymap, "C-x a").as_slice(),
+            [Lookup::Pending, Lookup::Undefined(keys)] if keys.len() == 2
+        ));
+        assert!(matches!(
+            press(&mut keymap, "a C-k").as_slice(),
+            [Lookup::Unbound, Lookup::Commands(_)]
+        ));
+        assert!(keymap.pending().is_empty());
+    }
+}
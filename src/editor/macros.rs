@@ -0,0 +1,313 @@
+use crate::editor::editorcommand::Direction::{
+    Down, End, FileEnd, FileStart, Home, Left, NextWordStart, PageDown, PageUp, Right, Up,
+    WordLeft, WordRight,
+};
+use crate::editor::editorcommand::{Direction, EditorCommand};
+use crate::editor::terminal::Position;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// The slot used by the bindings that do not name one
+pub const DEFAULT_SLOT: char = '0';
+
+/// The names of the directions in the macros file
+const DIRECTIONS: [(&str, Direction); 13] = [
+    ("page-up", PageUp),
+    ("page-down", PageDown),
+    ("home", Home),
+    ("end", End),
+    ("up", Up),
+    ("left", Left),
+    ("right", Right),
+    ("down", Down),
+    ("word-left", WordLeft),
+    ("word-right", WordRight),
+    ("next-word-start", NextWordStart),
+    ("file-start", FileStart),
+    ("file-end", FileEnd),
+];
+
+/// The names of the commands without arguments in the macros file
+const COMMANDS: [(&str, EditorCommand); 24] = [
+    ("enter", EditorCommand::Enter),
+    ("delete", EditorCommand::Delete),
+    ("backspace", EditorCommand::Backspace),
+    ("delete-word-backward", EditorCommand::DeleteWordBackward),
+    ("delete-word-forward", EditorCommand::DeleteWordForward),
+    (
+        "jump-to-matching-bracket",
+        EditorCommand::JumpToMatchingBracket,
+    ),
+    ("start-selection", EditorCommand::StartSelection),
+    ("start-line-selection", EditorCommand::StartLineSelection),
+    ("clear-selection", EditorCommand::ClearSelection),
+    ("collapse-selection", EditorCommand::CollapseSelection),
+    ("copy", EditorCommand::Copy),
+    ("cut", EditorCommand::Cut),
+    ("paste", EditorCommand::Paste),
+    ("paste-after", EditorCommand::PasteAfter),
+    ("open-line-below", EditorCommand::OpenLineBelow),
+    ("open-line-above", EditorCommand::OpenLineAbove),
+    ("kill-line", EditorCommand::KillLine),
+    ("yank-pop", EditorCommand::YankPop),
+    ("add-caret-above", EditorCommand::AddCaretAbove),
+    ("add-caret-below", EditorCommand::AddCaretBelow),
+    ("add-next-occurrence", EditorCommand::AddNextOccurrence),
+    ("toggle-read-only", EditorCommand::ToggleReadOnly),
+    ("toggle-follow", EditorCommand::ToggleFollow),
+    ("toggle-hex", EditorCommand::ToggleHex),
+];
+
+/// Writes `command` as a line of the macros file, like `move word-left` or `insert x`.
+/// Returns `None` for the commands that are not recorded: quitting, resizing, prompts and the macro commands themselves.
+fn format_command(command: &EditorCommand) -> Option<String> {
+    let direction_name = |direction: &Direction| {
+        DIRECTIONS
+            .iter()
+            .find(|(_, named)| named == direction)
+            .map(|(name, _)| *name)
+    };
+    match command {
+        EditorCommand::Move(direction) => Some(format!("move {}", direction_name(direction)?)),
+        EditorCommand::Select(direction) => Some(format!("select {}", direction_name(direction)?)),
+        EditorCommand::SelectInLine(direction) => {
+            Some(format!("select-in-line {}", direction_name(direction)?))
+        }
+        EditorCommand::Scroll(direction) => Some(format!("scroll {}", direction_name(direction)?)),
+        EditorCommand::SelectBlock(direction) => {
+            Some(format!("select-block {}", direction_name(direction)?))
+        }
+        EditorCommand::Insert(character) => Some(format!("insert {character}")),
+        EditorCommand::Click(Position { col, row }) => Some(format!("click {col} {row}")),
+        EditorCommand::DoubleClick(Position { col, row }) => {
+            Some(format!("double-click {col} {row}"))
+        }
+        EditorCommand::Drag(Position { col, row }) => Some(format!("drag {col} {row}")),
+        EditorCommand::DragBlock(Position { col, row }) => Some(format!("drag-block {col} {row}")),
+        EditorCommand::ToggleCaret(Position { col, row }) => {
+            Some(format!("toggle-caret {col} {row}"))
+        }
+        EditorCommand::GoTo(target) => Some(format!("go-to {target}")),
+        _ => COMMANDS
+            .iter()
+            .find(|(_, named)| named == command)
+            .map(|(name, _)| (*name).to_string()),
+    }
+}
+
+/// Reads a line written by [`format_command`]
+fn parse_command(line: &str) -> Option<EditorCommand> {
+    let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+    let direction = || {
+        DIRECTIONS
+            .iter()
+            .find(|(direction_name, _)| *direction_name == argument)
+            .map(|(_, direction)| *direction)
+    };
+    let position = || {
+        let (col, row) = argument.split_once(' ')?;
+        Some(Position {
+            col: col.parse().ok()?,
+            row: row.parse().ok()?,
+        })
+    };
+    match name {
+        "move" => direction().map(EditorCommand::Move),
+        "select" => direction().map(EditorCommand::Select),
+        "select-in-line" => direction().map(EditorCommand::SelectInLine),
+        "scroll" => direction().map(EditorCommand::Scroll),
+        "select-block" => direction().map(EditorCommand::SelectBlock),
+        "insert" => {
+            let mut characters = argument.chars();
+            match (characters.next(), characters.next()) {
+                (Some(character), None) => Some(EditorCommand::Insert(character)),
+                _ => None,
+            }
+        }
+        "click" => position().map(EditorCommand::Click),
+        "double-click" => position().map(EditorCommand::DoubleClick),
+        "drag" => position().map(EditorCommand::Drag),
+        "drag-block" => position().map(EditorCommand::DragBlock),
+        "toggle-caret" => position().map(EditorCommand::ToggleCaret),
+        "go-to" => argument.parse().ok().map(EditorCommand::GoTo),
+        _ => COMMANDS
+            .iter()
+            .find(|(command_name, _)| *command_name == line)
+            .map(|(_, command)| command.clone()),
+    }
+}
+
+/// This records the commands run by the editor into slots named by a character, to play them again.
+/// Macros loaded from a file are written back to it each time a recording ends, so they are kept across sessions.
+#[derive(Default)]
+pub struct Macros {
+    slots: BTreeMap<char, Vec<EditorCommand>>,
+    /// The slot being recorded, with the commands recorded so far
+    recording: Option<(char, Vec<EditorCommand>)>,
+    /// The slot played when none is given: the last one recorded or played
+    last_slot: Option<char>,
+    path: Option<PathBuf>,
+}
+
+impl Macros {
+    /// Returns where macros are saved: `text-editor/macros` in the configuration directory of the user
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|directory| directory.join("text-editor").join("macros"))
+    }
+
+    /// Loads the macros saved in `path`, the file is created when a macro is recorded if it does not exist.
+    /// The file has a line with the name of each slot in brackets, like `[a]`, followed by the commands of the slot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `path` exists but can not be read
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let mut macros = Self {
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        };
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(macros),
+            Err(error) => return Err(error),
+        };
+        let mut slot = None;
+        for line in contents.lines() {
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                let mut characters = name.chars();
+                slot = match (characters.next(), characters.next()) {
+                    (Some(character), None) => Some(character),
+                    _ => None,
+                };
+            } else if let (Some(slot), Some(command)) = (slot, parse_command(line)) {
+                macros.slots.entry(slot).or_default().push(command);
+            }
+        }
+        Ok(macros)
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut contents = String::new();
+        for (slot, commands) in &self.slots {
+            contents.push('[');
+            contents.push(*slot);
+            contents.push_str("]\n");
+            for line in commands.iter().filter_map(format_command) {
+                contents.push_str(&line);
+                contents.push('\n');
+            }
+        }
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, contents)
+    }
+
+    #[must_use]
+    pub fn recording_slot(&self) -> Option<char> {
+        self.recording.as_ref().map(|(slot, _)| *slot)
+    }
+
+    /// Starts recording into `slot`, dropping the recording in progress if there is one
+    pub fn start_recording(&mut self, slot: char) {
+        self.recording = Some((slot, Vec::new()));
+    }
+
+    /// Keeps the recorded commands in their slot and saves the macros.
+    /// Like in emacs, an empty recording leaves the slot as it was.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the macros can not be written to their file
+    pub fn stop_recording(&mut self) -> Result<(), Error> {
+        let Some((slot, commands)) = self.recording.take() else {
+            return Ok(());
+        };
+        if commands.is_empty() {
+            return Ok(());
+        }
+        self.slots.insert(slot, commands);
+        self.last_slot = Some(slot);
+        self.save()
+    }
+
+    /// Adds `command` to the recording in progress, if it is a command that can be saved
+    pub fn record(&mut self, command: &EditorCommand) {
+        if let Some((_, commands)) = &mut self.recording {
+            if format_command(command).is_some() {
+                commands.push(command.clone());
+            }
+        }
+    }
+
+    /// Returns the commands to play for `slot`, or for the last slot recorded or played
+    pub fn commands(&mut self, slot: Option<char>) -> Vec<EditorCommand> {
+        let Some(slot) = slot.or(self.last_slot) else {
+            return Vec::new();
+        };
+        let Some(commands) = self.slots.get(&slot) else {
+            return Vec::new();
+        };
+        self.last_slot = Some(slot);
+        commands.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::test::TempDir;
+
+    #[test]
+    fn test_format_and_parse_commands() {
+        let commands = [
+            EditorCommand::Move(WordLeft),
+            EditorCommand::Select(FileEnd),
+            EditorCommand::Insert(' '),
+            EditorCommand::Insert('𝒻'),
+            EditorCommand::Click(Position { col: 3, row: 7 }),
+            EditorCommand::KillLine,
+            EditorCommand::GoTo("-4:2".parse().unwrap()),
+        ];
+        for command in commands {
+            let line = format_command(&command).unwrap();
+            assert_eq!(parse_command(&line), Some(command));
+        }
+        assert_eq!(format_command(&EditorCommand::Quit), None);
+        assert_eq!(parse_command("insert xy"), None);
+        assert_eq!(parse_command("move sideways"), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let directory = TempDir::new("macros");
+        let path = directory.join("macros");
+        let mut macros = Macros::load(&path).unwrap();
+        macros.start_recording('a');
+        macros.record(&EditorCommand::Insert('x'));
+        macros.record(&EditorCommand::Quit);
+        macros.record(&EditorCommand::Move(Down));
+        macros.stop_recording().unwrap();
+        macros.start_recording('b');
+        macros.stop_recording().unwrap();
+        let mut loaded = Macros::load(&path).unwrap();
+        assert_eq!(
+            loaded.commands(Some('a')),
+            vec![EditorCommand::Insert('x'), EditorCommand::Move(Down)]
+        );
+        assert!(loaded.commands(Some('b')).is_empty());
+        assert_eq!(loaded.commands(None).len(), 2);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::editor::backend::Backend;
+use crate::editor::terminal::{Position, Size};
+use crossterm::style::Color;
+use std::io::Error;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub reverse: bool,
+}
+
+impl Style {
+    #[must_use]
+    pub fn foreground(color: Color) -> Self {
+        Self {
+            foreground: Some(color),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn background(color: Color) -> Self {
+        Self {
+            background: Some(color),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn reversed() -> Self {
+        Self {
+            reverse: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// A cell of the screen.
+/// A full-width grapheme takes two cells: the second one is a continuation, with an empty grapheme
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub grapheme: String,
+    pub style: Style,
+}
+
+impl Cell {
+    fn is_continuation(&self) -> bool {
+        self.grapheme.is_empty()
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            grapheme: " ".to_string(),
+            style: Style::default(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Grid {
+    size: Size,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    fn new(size: Size) -> Self {
+        Self {
+            size,
+            cells: vec![Cell::default(); size.width.saturating_mul(size.height)],
+        }
+    }
+
+    fn get(&self, position: Position) -> Option<&Cell> {
+        if position.col >= self.size.width {
+            return None;
+        }
+        self.cells.get(
+            position
+                .row
+                .saturating_mul(self.size.width)
+                .saturating_add(position.col),
+        )
+    }
+
+    fn row_mut(&mut self, row: usize) -> Option<&mut [Cell]> {
+        if row >= self.size.height {
+            return None;
+        }
+        let start = row.saturating_mul(self.size.width);
+        self.cells
+            .get_mut(start..start.saturating_add(self.size.width))
+    }
+}
+
+/// This represents what should be on the screen.
+/// Components write the next frame into it and [`Screen::present`] only sends the cells
+/// that differ from the last presented frame to the terminal.
+#[derive(Default)]
+pub struct Screen {
+    current: Grid,
+    /// `None` when we don't know what the terminal shows, for example after a resize
+    previous: Option<Grid>,
+}
+
+impl Screen {
+    pub fn resize(&mut self, size: Size) {
+        self.current = Grid::new(size);
+        self.previous = None;
+    }
+
+    #[must_use]
+    pub fn size(&self) -> Size {
+        self.current.size
+    }
+
+    /// Replaces the content of `row` with `text`
+    pub fn print_row(&mut self, row: usize, text: &str) {
+        self.print_styled_row(row, text, Style::default());
+    }
+
+    /// Replaces the content of `row` with `text`, drawn with `style`.
+    /// The end of the row is cleared, full-width graphemes that do not fit are dropped.
+    pub fn print_styled_row(&mut self, row: usize, text: &str, style: Style) {
+        let Some(cells) = self.current.row_mut(row) else {
+            return;
+        };
+        let mut col: usize = 0;
+        for grapheme in text.graphemes(true) {
+            let width = grapheme.width().clamp(1, 2);
+            let Some(target) = cells.get_mut(col..col.saturating_add(width)) else {
+                break;
+            };
+            for (index, cell) in target.iter_mut().enumerate() {
+                cell.grapheme = if index == 0 {
+                    grapheme.to_string()
+                } else {
+                    String::new()
+                };
+                cell.style = style;
+            }
+            col = col.saturating_add(width);
+        }
+        for cell in cells.iter_mut().skip(col) {
+            *cell = Cell::default();
+        }
+    }
+
+    /// Changes the style of the cells of `row` in `columns`, keeping their graphemes
+    pub fn set_style(&mut self, row: usize, columns: Range<usize>, style: Style) {
+        if let Some(cells) = self.current.row_mut(row) {
+            for cell in cells
+                .iter_mut()
+                .skip(columns.start)
+                .take(columns.end.saturating_sub(columns.start))
+            {
+                cell.style = style;
+            }
+        }
+    }
+
+    /// Returns the position of every cell that has to be printed to go from the last presented frame to the current one.
+    /// Continuation cells are never returned: printing the full-width grapheme before them updates them as well.
+    fn changed_cells(&self, previous: &Grid) -> Vec<Position> {
+        let Size { width, height } = self.current.size;
+        let mut changed = Vec::new();
+        for row in 0..height {
+            let mut col = 0;
+            while col < width {
+                let position = Position { col, row };
+                let next = Position {
+                    col: col.saturating_add(1),
+                    row,
+                };
+                let cell_width = match self.current.get(next) {
+                    Some(cell) if cell.is_continuation() => 2,
+                    _ => 1,
+                };
+                let is_changed = [position, next]
+                    .iter()
+                    .take(cell_width)
+                    .any(|position| self.current.get(*position) != previous.get(*position));
+                if is_changed {
+                    changed.push(position);
+                }
+                col = col.saturating_add(cell_width);
+            }
+        }
+        changed
+    }
+
+    /// Queues the commands needed to show the current frame, moving the cursor and changing colors only when needed.
+    /// Nothing is flushed, see [`Backend::execute`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if any of the [`Backend`] functions fail
+    pub fn present(&mut self, backend: &mut impl Backend) -> Result<(), Error> {
+        let previous = if let Some(previous) = self.previous.take() {
+            previous
+        } else {
+            backend.set_style(Style::default())?;
+            backend.clear_screen()?;
+            Grid::new(self.current.size)
+        };
+        let mut cursor: Option<Position> = None;
+        let mut current_style = Style::default();
+        for position in self.changed_cells(&previous) {
+            let Some(cell) = self.current.get(position) else {
+                continue;
+            };
+            if cursor != Some(position) {
+                backend.move_cursor_to(position)?;
+            }
+            if cell.style != current_style {
+                backend.set_style(cell.style)?;
+                current_style = cell.style;
+            }
+            backend.print(&cell.grapheme)?;
+            let cell_width = cell.grapheme.width().clamp(1, 2);
+            cursor = Some(Position {
+                col: position.col.saturating_add(cell_width),
+                row: position.row,
+            });
+        }
+        if current_style != Style::default() {
+            backend.set_style(Style::default())?;
+        }
+        self.previous = Some(self.current.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set_up() -> (Screen, Grid) {
+        let mut screen = Screen::default();
+        screen.resize(Size {
+            width: 10,
+            height: 3,
+        });
+        let previous = screen.current.clone();
+        (screen, previous)
+    }
+
+    #[test]
+    fn test_only_changed_cells() {
+        let (mut screen, mut previous) = set_up();
+        screen.print_row(1, "hello");
+        previous.clone_from(&screen.current);
+        screen.print_row(1, "hallo");
+        assert_eq!(
+            screen.changed_cells(&previous),
+            vec![Position { col: 1, row: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_full_width_graphemes() {
+        let (mut screen, mut previous) = set_up();
+        screen.print_row(0, "ab");
+        previous.clone_from(&screen.current);
+        screen.print_row(0, "你");
+        assert_eq!(
+            screen.changed_cells(&previous),
+            vec![Position { col: 0, row: 0 }]
+        );
+        previous.clone_from(&screen.current);
+        screen.print_row(0, "a你");
+        assert_eq!(
+            screen.changed_cells(&previous),
+            vec![Position { col: 0, row: 0 }, Position { col: 1, row: 0 }]
+        );
+    }
+
+    #[test]
+    fn test_style_change() {
+        let (mut screen, mut previous) = set_up();
+        screen.print_row(2, "~");
+        previous.clone_from(&screen.current);
+        screen.print_styled_row(2, "~", Style::foreground(Color::Green));
+        assert_eq!(
+            screen.changed_cells(&previous),
+            vec![Position { col: 0, row: 2 }]
+        );
+    }
+
+    #[test]
+    fn test_wide_grapheme_does_not_fit() {
+        let (mut screen, _) = set_up();
+        screen.print_row(0, "123456789你");
+        let last = screen.current.get(Position { col: 9, row: 0 }).unwrap();
+        assert_eq!(last, &Cell::default());
+    }
+}
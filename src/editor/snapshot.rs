@@ -0,0 +1,203 @@
+//! Snapshot tests of rendered frames.
+//! Each test loads a file from `text-test/`, applies [`EditorCommand`]s and compares the frame drawn on a
+//! [`MemoryBackend`] with `text-test/snapshots/<name>.snap`.
+//! Missing snapshots are written and the test fails so they can be reviewed,
+//! run the tests with `UPDATE_SNAPSHOTS=1` to overwrite the existing ones.
+
+use crate::editor::arguments::Options;
+use crate::editor::backend::memory::MemoryBackend;
+use crate::editor::editorcommand::Direction::{Down, End, PageDown, Right};
+use crate::editor::editorcommand::EditorCommand;
+use crate::editor::screen::Style;
+use crate::editor::terminal::{Position, Size};
+use crate::editor::Editor;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+const SNAPSHOT_DIRECTORY: &str = "./text-test/snapshots";
+
+fn run(file_name: Option<&str>, size: Size, commands: Vec<EditorCommand>) -> String {
+    let mut args = vec!["text-editor".to_string()];
+    args.extend(file_name.map(ToString::to_string));
+    let mut editor =
+        Editor::with_backend(MemoryBackend::new(size), &Options::parse(&args).unwrap()).unwrap();
+    for command in commands {
+        editor.handle_command(command);
+    }
+    editor.refresh_screen();
+    frame_to_string(editor.backend(), size)
+}
+
+fn style_to_string(style: Style) -> String {
+    let mut result = Vec::new();
+    if let Some(foreground) = style.foreground {
+        result.push(format!("fg:{foreground:?}"));
+    }
+    if let Some(background) = style.background {
+        result.push(format!("bg:{background:?}"));
+    }
+    if style.reverse {
+        result.push("reverse".to_string());
+    }
+    result.join(" ")
+}
+
+/// Writes the text of every row, then the caret, then every run of cells that is not drawn with the default style
+fn frame_to_string(backend: &MemoryBackend, size: Size) -> String {
+    let mut frame = String::new();
+    for row in 0..size.height {
+        let _ = writeln!(frame, "{row:>3}|{}|", backend.row_text(row));
+    }
+    let Position { col, row } = backend.cursor_position();
+    let visibility = if backend.is_cursor_visible() {
+        "visible"
+    } else {
+        "hidden"
+    };
+    let _ = writeln!(frame, "caret: {row}:{col} {visibility}");
+    for row in 0..size.height {
+        let mut start = 0;
+        let mut current = Style::default();
+        for col in 0..=size.width {
+            let style = backend
+                .cell(Position { col, row })
+                .map_or(Style::default(), |cell| cell.style);
+            if style != current {
+                if current != Style::default() {
+                    let _ = writeln!(
+                        frame,
+                        "style: {row}:{start}..{col} {}",
+                        style_to_string(current)
+                    );
+                }
+                start = col;
+                current = style;
+            }
+        }
+    }
+    frame
+}
+
+fn assert_snapshot(name: &str, frame: &str) {
+    let path = PathBuf::from(SNAPSHOT_DIRECTORY).join(format!("{name}.snap"));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if !update => {
+            assert_eq!(
+                expected.replace("\r\n", "\n"),
+                frame,
+                "Frame differs from snapshot {name}, run with UPDATE_SNAPSHOTS=1 to accept it"
+            );
+        }
+        existing => {
+            std::fs::create_dir_all(SNAPSHOT_DIRECTORY).unwrap();
+            std::fs::write(&path, frame).unwrap();
+            assert!(
+                existing.is_ok(),
+                "New snapshot {name} written, review it and run the tests again"
+            );
+        }
+    }
+}
+
+const SMALL: Size = Size {
+    width: 30,
+    height: 8,
+};
+
+#[test]
+fn test_welcome() {
+    assert_snapshot("welcome", &run(None, SMALL, vec![]));
+}
+
+#[test]
+fn test_wide_graphemes() {
+    let mut commands: Vec<EditorCommand> = (0..7).map(|_| EditorCommand::Move(Down)).collect();
+    commands.extend((0..5).map(|_| EditorCommand::Move(Right)));
+    assert_snapshot(
+        "wide_graphemes",
+        &run(Some("./text-test/test-2.txt"), SMALL, commands),
+    );
+}
+
+#[test]
+fn test_wide_graphemes_scrolled() {
+    let mut commands: Vec<EditorCommand> = (0..7).map(|_| EditorCommand::Move(Down)).collect();
+    commands.extend((0..15).map(|_| EditorCommand::Move(Right)));
+    assert_snapshot(
+        "wide_graphemes_scrolled",
+        &run(Some("./text-test/test-2.txt"), SMALL, commands),
+    );
+}
+
+#[test]
+fn test_ambiguous_graphemes_end_of_line() {
+    let mut commands: Vec<EditorCommand> = (0..11).map(|_| EditorCommand::Move(Down)).collect();
+    commands.push(EditorCommand::Move(End));
+    assert_snapshot(
+        "ambiguous_graphemes_end_of_line",
+        &run(Some("./text-test/test-2.txt"), SMALL, commands),
+    );
+}
+
+#[test]
+fn test_page_down() {
+    let commands = vec![
+        EditorCommand::Move(PageDown),
+        EditorCommand::Move(PageDown),
+        EditorCommand::Move(Down),
+    ];
+    assert_snapshot(
+        "page_down",
+        &run(Some("./text-test/test-3.txt"), SMALL, commands),
+    );
+}
+
+#[test]
+fn test_drag_selection_with_full_width_graphemes() {
+    let commands = vec![
+        EditorCommand::Click(Position { col: 3, row: 6 }),
+        EditorCommand::Drag(Position { col: 5, row: 7 }),
+    ];
+    assert_snapshot(
+        "drag_selection_with_full_width_graphemes",
+        &run(Some("./text-test/test-2.txt"), SMALL, commands),
+    );
+}
+
+#[test]
+fn test_matching_brackets() {
+    let mut commands: Vec<EditorCommand> = (0..13).map(|_| EditorCommand::Move(Down)).collect();
+    commands.push(EditorCommand::Move(End));
+    commands.push(EditorCommand::JumpToMatchingBracket);
+    assert_snapshot(
+        "matching_brackets",
+        &run(Some("./text-test/test-4.txt"), SMALL, commands),
+    );
+}
+
+#[test]
+fn test_multiple_carets() {
+    let commands = vec![
+        EditorCommand::Move(Down),
+        EditorCommand::AddNextOccurrence,
+        EditorCommand::AddNextOccurrence,
+        EditorCommand::AddCaretBelow,
+    ];
+    assert_snapshot(
+        "multiple_carets",
+        &run(Some("./text-test/test.txt"), SMALL, commands),
+    );
+}
+
+#[test]
+fn test_block_selection_with_full_width_graphemes() {
+    let commands = vec![
+        EditorCommand::Click(Position { col: 3, row: 6 }),
+        EditorCommand::DragBlock(Position { col: 6, row: 8 }),
+    ];
+    assert_snapshot(
+        "block_selection_with_full_width_graphemes",
+        &run(Some("./text-test/test-2.txt"), SMALL, commands),
+    );
+}
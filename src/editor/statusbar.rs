@@ -0,0 +1,68 @@
+use crate::editor::documentstatus::DocumentStatus;
+use crate::editor::screen::{Screen, Style};
+use crate::editor::terminal::Size;
+
+/// This represents the bar at the bottom of the screen giving information about the document
+#[derive(Default)]
+pub struct StatusBar {
+    current_status: DocumentStatus,
+    /// Shown instead of the status until the next key, like an error
+    message: Option<String>,
+    needs_redraw: bool,
+    width: usize,
+    position_y: usize,
+}
+
+impl StatusBar {
+    /// The status bar takes the last row of `size`
+    pub fn resize(&mut self, size: Size) {
+        self.width = size.width;
+        self.position_y = size.height.saturating_sub(1);
+        self.needs_redraw = true;
+    }
+
+    pub fn set_needs_redraw(&mut self) {
+        self.needs_redraw = true;
+    }
+
+    pub fn update_status(&mut self, new_status: DocumentStatus) {
+        if new_status != self.current_status {
+            self.current_status = new_status;
+            self.needs_redraw = true;
+        }
+    }
+
+    pub fn set_message(&mut self, message: Option<String>) {
+        if message != self.message {
+            self.message = message;
+            self.needs_redraw = true;
+        }
+    }
+
+    pub fn render(&mut self, screen: &mut Screen) {
+        if !self.needs_redraw || self.width == 0 {
+            return;
+        }
+        if let Some(message) = &self.message {
+            screen.print_row(self.position_y, message);
+            self.needs_redraw = false;
+            return;
+        }
+        let status = &self.current_status;
+        let left = format!(
+            "{}{} - {}{}",
+            status.mode_to_string(),
+            status.file_name_to_string(),
+            status.line_count_to_string(),
+            status.flags_to_string()
+        );
+        let right = status.position_indicator_to_string();
+        let remainder_len = self.width.saturating_sub(left.chars().count());
+        let mut status_text = format!("{left}{right:>remainder_len$}");
+        if let Some((byte_index, _)) = status_text.char_indices().nth(self.width) {
+            status_text.truncate(byte_index);
+        }
+        screen.print_styled_row(self.position_y, &status_text, Style::reversed());
+        self.needs_redraw = false;
+    }
+}
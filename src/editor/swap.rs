@@ -0,0 +1,400 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+
+/// The swap files of all the buffers, for [`flush_all`] to reach them from the panic hook
+static REGISTRY: Mutex<Vec<Weak<Mutex<Pending>>>> = Mutex::new(Vec::new());
+/// Numbers the swap files of the buffers without a file, see [`unnamed_path`]
+static UNNAMED_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// The extensions of the swap files of a file, tried in turn like in vim: a swap file left by another editor
+/// is never written over, the user may still want to recover it
+const EXTENSIONS: [&str; 4] = ["swp", "swo", "swn", "swm"];
+
+/// The text of a buffer, and where its swap file was written
+#[derive(Default)]
+struct Pending {
+    /// Empty for a buffer without a file
+    file_name: String,
+    /// The text given since the last write, `None` once it is written
+    text: Option<String>,
+    path: Option<PathBuf>,
+}
+
+impl Pending {
+    fn write(&mut self) -> Result<(), Error> {
+        let Some(text) = self.text.take() else {
+            return Ok(());
+        };
+        let path = match &self.path {
+            Some(path) => write_private(path, &text, false).map(|()| path.clone()),
+            None => write_new(&self.file_name, &text),
+        };
+        match path {
+            Ok(path) => {
+                self.path = Some(path);
+                Ok(())
+            }
+            Err(error) => {
+                self.text = Some(text);
+                Err(error)
+            }
+        }
+    }
+}
+
+/// This keeps a copy of the unsaved text of a buffer in a swap file, to recover it if the editor dies.
+/// The swap file is written next to the file, as `.name.swp`, or in the cache directory of the user
+/// if the directory of the file can not be written. If a swap file is already there, `.name.swo` is used, and so on.
+/// The text of a buffer without a file, like the one read from stdin, is kept in the cache directory,
+/// so it is not lost either when the editor is killed: the next editor started without a file offers it.
+#[derive(Default)]
+pub struct Swap {
+    pending: Arc<Mutex<Pending>>,
+    is_registered: bool,
+    /// The revision of the buffer whose text was last given
+    revision: Option<u64>,
+    /// [`Swap::check`] was called, a swap file left by another editor is only reported once
+    is_checked: bool,
+}
+
+impl Swap {
+    fn pending(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keeps `text`, the text of the buffer at `revision`, until the next [`Swap::write`].
+    /// The text is only built if the revision changed. `file_name` is empty if the buffer has no file.
+    pub fn update(&mut self, file_name: &str, revision: u64, text: impl FnOnce() -> String) {
+        if self.revision == Some(revision) {
+            return;
+        }
+        self.revision = Some(revision);
+        let mut pending = self.pending();
+        if pending.file_name != file_name {
+            pending.path = None;
+            file_name.clone_into(&mut pending.file_name);
+        }
+        pending.text = Some(text());
+        drop(pending);
+        if !self.is_registered {
+            let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+            registry.retain(|swap| swap.strong_count() > 0);
+            registry.push(Arc::downgrade(&self.pending));
+            self.is_registered = true;
+        }
+    }
+
+    /// Writes the text given since the last call to the swap file
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the swap file can be written neither next to the file nor in the cache directory
+    pub fn write(&mut self) -> Result<(), Error> {
+        self.pending().write()
+    }
+
+    /// Deletes the swap file, once the text was saved or the editor quits
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the swap file exists but can not be deleted
+    pub fn remove(&mut self) -> Result<(), Error> {
+        self.revision = None;
+        let mut pending = self.pending();
+        pending.text = None;
+        match pending.path.take().map(fs::remove_file) {
+            Some(Err(error)) if error.kind() != ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Makes `path`, a swap file left for the same file, the swap file of the buffer, after it was recovered
+    pub fn adopt(&mut self, path: &Path) {
+        self.pending().path = Some(path.to_path_buf());
+    }
+
+    /// Returns the swap file left for `file_name` if it is newer than the file, the first time it is called.
+    /// If `file_name` is empty, it is the newest one left for a buffer without a file, see [`find_unnamed`].
+    pub fn check(&mut self, file_name: &str) -> Option<PathBuf> {
+        if std::mem::replace(&mut self.is_checked, true) {
+            return None;
+        }
+        if file_name.is_empty() {
+            return find_unnamed(&cache_directory()?);
+        }
+        find(file_name)
+    }
+
+    /// Where the text was last written, if it was
+    #[must_use]
+    pub fn path(&self) -> Option<PathBuf> {
+        self.pending().path.clone()
+    }
+}
+
+/// Writes the text not written yet of every buffer, the editor is about to die
+pub fn flush_all() {
+    // The registry or a swap file could be locked by the code that panicked, they are skipped then
+    let Ok(registry) = REGISTRY.try_lock() else {
+        return;
+    };
+    for pending in registry.iter().filter_map(Weak::upgrade) {
+        if let Ok(mut pending) = pending.try_lock() {
+            let _ = pending.write();
+        }
+    }
+}
+
+/// Returns `.name.swp` and the other swap files of `file_name` in its directory, see [`EXTENSIONS`]
+fn local_paths(file_name: &str) -> Vec<PathBuf> {
+    let path = Path::new(file_name);
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    EXTENSIONS
+        .iter()
+        .map(|extension| path.with_file_name(format!(".{name}.{extension}")))
+        .collect()
+}
+
+/// Returns `text-editor/swap` in the cache directory of the user
+fn cache_directory() -> Option<PathBuf> {
+    Some(cache_home()?.join("text-editor").join("swap"))
+}
+
+#[cfg(not(test))]
+fn cache_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+}
+
+/// The tests must not be offered the swap files the user left, nor leave theirs among them
+#[cfg(test)]
+// It has the signature of the one the editor uses, which finds no cache directory without a home
+#[allow(clippy::unnecessary_wraps)]
+fn cache_home() -> Option<PathBuf> {
+    Some(std::env::temp_dir().join(format!("text-editor-cache-{}", std::process::id())))
+}
+
+/// Returns the swap files for `file_name` in the cache directory, see [`cache_directory`] and [`EXTENSIONS`].
+/// Like in vim, their name is the absolute path of the file with `%` instead of the separators.
+fn cache_paths(file_name: &str) -> Vec<PathBuf> {
+    let name = std::path::absolute(file_name)
+        .ok()
+        .and_then(|absolute| Some(absolute.to_str()?.replace(std::path::MAIN_SEPARATOR, "%")));
+    let (Some(name), Some(directory)) = (name, cache_directory()) else {
+        return Vec::new();
+    };
+    EXTENSIONS
+        .iter()
+        .map(|extension| directory.join(format!("{name}.{extension}")))
+        .collect()
+}
+
+/// Returns a new swap file for a buffer without a file in the cache directory, see [`cache_directory`].
+/// Its name has the process id, so the editors running at the same time do not write to the same file.
+fn unnamed_path() -> Option<PathBuf> {
+    let number = UNNAMED_COUNT.fetch_add(1, Ordering::Relaxed);
+    Some(cache_directory()?.join(format!("unnamed-{}-{number}.swp", std::process::id())))
+}
+
+/// Returns the process id in `name` if it is the name of a swap file of a buffer without a file, see [`unnamed_path`]
+fn unnamed_pid(name: &str) -> Option<u32> {
+    let (pid, _) = name
+        .strip_prefix("unnamed-")?
+        .strip_suffix(".swp")?
+        .split_once('-')?;
+    pid.parse().ok()
+}
+
+/// Returns the newest swap file of a buffer without a file in `directory`, left by an editor that is not running anymore
+fn find_unnamed(directory: &Path) -> Option<PathBuf> {
+    fs::read_dir(directory)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(unnamed_pid)
+                .is_some_and(|pid| !is_running(pid))
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Tells whether the process `pid` exists, checked by sending it no signal
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: the null signal is not sent, `kill` only checks that the process exists
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a way to check the other processes, only the editor itself is known to be running
+#[cfg(not(unix))]
+fn is_running(pid: u32) -> bool {
+    pid == std::process::id()
+}
+
+/// Writes the first swap file of `file_name` and returns where it is
+fn write_new(file_name: &str, text: &str) -> Result<PathBuf, Error> {
+    if file_name.is_empty() {
+        return write_in_cache(unnamed_path().into_iter().collect(), text);
+    }
+    match write_first_free(local_paths(file_name), text) {
+        Ok(path) => Ok(path),
+        Err(local_error) => write_in_cache(cache_paths(file_name), text).map_err(|_| local_error),
+    }
+}
+
+/// Writes `text` to the first of `paths` in the cache directory that does not exist, creating the directory if needed
+fn write_in_cache(paths: Vec<PathBuf>, text: &str) -> Result<PathBuf, Error> {
+    let Some(directory) = paths.first().and_then(|path| path.parent()) else {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            "The cache directory is not known",
+        ));
+    };
+    fs::create_dir_all(directory)?;
+    write_first_free(paths, text)
+}
+
+/// Writes `text` to the first of `paths` that does not exist and returns it
+fn write_first_free(paths: Vec<PathBuf>, text: &str) -> Result<PathBuf, Error> {
+    for path in paths {
+        match write_private(&path, text, true) {
+            Ok(()) => return Ok(path),
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Err(Error::new(
+        ErrorKind::AlreadyExists,
+        "Every swap file name is taken",
+    ))
+}
+
+/// Writes `text` to `path`, which only the user can read or write: the text of the buffer may be private.
+/// A new file is only created if there is none at `path` yet.
+fn write_private(path: &Path, text: &str, is_new: bool) -> Result<(), Error> {
+    let mut options = OpenOptions::new();
+    if is_new {
+        options.write(true).create_new(true);
+    } else {
+        options.write(true).create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // A swap file left with other permissions is not reopened with the mode
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(text.as_bytes())
+}
+
+/// Returns the newest swap file of `file_name`, if it is newer than the file or the file does not exist
+fn find(file_name: &str) -> Option<PathBuf> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+    let file_modified = modified(Path::new(file_name)).ok();
+    local_paths(file_name)
+        .into_iter()
+        .chain(cache_paths(file_name))
+        .filter_map(|path| Some((modified(&path).ok()?, path)))
+        .filter(|(swap_modified, _)| file_modified.is_none_or(|file| *swap_modified >= file))
+        .max_by_key(|(swap_modified, _)| *swap_modified)
+        .map(|(_, path)| path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::test::TempDir;
+
+    #[test]
+    fn test_write_check_and_remove() {
+        let directory = TempDir::new("swap");
+        let file_path = directory.join("notes.txt");
+        let file_name = file_path.to_str().unwrap();
+        fs::write(&file_path, "old\n").unwrap();
+        let mut swap = Swap::default();
+        swap.update(file_name, 1, || "new\n".to_string());
+        swap.update(file_name, 1, || panic!("The text did not change"));
+        swap.write().unwrap();
+        let swap_path = directory.join(".notes.txt.swp");
+        assert_eq!(fs::read_to_string(&swap_path).unwrap(), "new\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&swap_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(Swap::default().check(file_name), Some(swap_path.clone()));
+        let mut other = Swap::default();
+        other.check(file_name);
+        assert_eq!(other.check(file_name), None);
+        swap.update(file_name, 2, || "newer\n".to_string());
+        flush_all();
+        assert_eq!(fs::read_to_string(&swap_path).unwrap(), "newer\n");
+        swap.remove().unwrap();
+        assert!(!swap_path.exists());
+        assert_eq!(find(file_name), None);
+    }
+
+    #[test]
+    fn test_keep_undecided_swap() {
+        let directory = TempDir::new("undecided-swap");
+        let file_path = directory.join("notes.txt");
+        let file_name = file_path.to_str().unwrap();
+        fs::write(&file_path, "old\n").unwrap();
+        let left_path = directory.join(".notes.txt.swp");
+        fs::write(&left_path, "left\n").unwrap();
+        let mut swap = Swap::default();
+        swap.update(file_name, 1, || "new\n".to_string());
+        swap.write().unwrap();
+        assert_eq!(fs::read_to_string(&left_path).unwrap(), "left\n");
+        let swap_path = directory.join(".notes.txt.swo");
+        assert_eq!(fs::read_to_string(&swap_path).unwrap(), "new\n");
+        swap.remove().unwrap();
+        assert!(left_path.exists());
+        assert!(!swap_path.exists());
+    }
+
+    #[test]
+    fn test_unnamed_paths() {
+        let Some(directory) = cache_directory() else {
+            return;
+        };
+        let first = unnamed_path().unwrap();
+        let second = unnamed_path().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), Some(directory.as_path()));
+        let name = first.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(&format!("unnamed-{}-", std::process::id())));
+        assert_eq!(unnamed_pid(name), Some(std::process::id()));
+        assert_eq!(unnamed_pid(".notes.txt.swp"), None);
+    }
+
+    #[test]
+    fn test_find_unnamed() {
+        let directory = TempDir::new("find-unnamed");
+        fs::write(directory.join(".notes.txt.swp"), "named\n").unwrap();
+        fs::write(
+            directory.join(&format!("unnamed-{}-0.swp", std::process::id())),
+            "running\n",
+        )
+        .unwrap();
+        assert_eq!(find_unnamed(directory.path()), None);
+        // No process can have this id, it is above the limit of Linux
+        let left = directory.join(&format!("unnamed-{}-3.swp", i32::MAX));
+        fs::write(&left, "left\n").unwrap();
+        assert_eq!(find_unnamed(directory.path()), Some(left));
+    }
+}
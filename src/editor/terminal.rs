@@ -1,15 +1,29 @@
-use crossterm::style::{Color, Colors};
+use crate::editor::backend::Backend;
+use crate::editor::screen::Style;
+use crossterm::cursor::SetCursorStyle;
+use crossterm::event::{poll, read, Event};
+use crossterm::style::{Attribute, Color, Colors};
 use crossterm::terminal::ClearType;
 use crossterm::{queue, Command};
-use std::io::{stdout, Error, Write};
+use std::fs::OpenOptions;
+use std::io::{stdin, stdout, Error, IsTerminal, Write};
+use std::time::Duration;
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub struct Position {
     pub col: usize,
     pub row: usize,
 }
 
 impl Position {
+    #[must_use]
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self {
+            row: self.row.saturating_add(other.row),
+            col: self.col.saturating_add(other.col),
+        }
+    }
+
     #[must_use]
     pub fn saturating_sub(self, other: Self) -> Self {
         Self {
@@ -25,16 +39,45 @@ pub struct Size {
     pub height: usize,
 }
 
+/// The shape of the cursor, `Default` being the one chosen by the user in their terminal
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum CursorShape {
+    #[default]
+    Default,
+    Block,
+    Bar,
+}
+
 /// This represents the terminal.
 /// Systems where usize < u16 might not be working due to conversion u16 as usize
+///
+/// Its functions can be used directly, for example from the panic hook, or through [`Backend`]
+#[derive(Default)]
 pub struct Terminal {}
 
 impl Terminal {
+    /// Keys are read from the standard input if it is the terminal, and from the controlling terminal otherwise,
+    /// so text can be piped to the editor while it is used.
+    ///
     /// # Errors
     ///
-    /// Will return `Err` is something goes wrong during the set-up
+    /// Will return `Err` is something goes wrong during the set-up, or if there is no terminal to read keys from
     pub fn set_up() -> Result<(), Error> {
+        #[cfg(unix)]
+        if !stdin().is_terminal() {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open("/dev/tty")
+                .map_err(|error| {
+                    Error::new(
+                        error.kind(),
+                        format!("No terminal to read keys from: {error}"),
+                    )
+                })?;
+        }
         Self::enter_alternate_screen()?;
+        Self::queue_command(crossterm::event::EnableMouseCapture)?;
         Self::enable_raw_mode()?;
         Self::clear_screen()?;
         Self::move_cursor_to(Position::default())
@@ -44,14 +87,32 @@ impl Terminal {
     ///
     /// Will return `Err` if something goes wrong when the terminal tries to terminate
     pub fn terminate() -> Result<(), Error> {
+        Self::set_cursor_shape(CursorShape::Default)?;
         Self::show_cursor()?;
         Self::reset_color()?;
+        Self::queue_command(crossterm::event::DisableMouseCapture)?;
         Self::leave_alternate_screen()?;
         Self::execute()?;
         Self::disable_raw_mode()?;
         Ok(())
     }
 
+    /// Restores the terminal and stops the editor, like Ctrl-Z in a shell does.
+    /// Once the shell resumes the editor, on `SIGCONT`, the terminal is set up again.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Terminal::terminate`] or [`Terminal::set_up`] fail
+    pub fn suspend() -> Result<(), Error> {
+        Self::terminate()?;
+        #[cfg(unix)]
+        // SAFETY: `raise` only sends a signal to the editor, it returns once the editor is resumed
+        unsafe {
+            libc::raise(libc::SIGTSTP);
+        }
+        Self::set_up()
+    }
+
     /// # Errors
     ///
     /// Will return `Err` if [`crossterm::terminal::disable_raw_mode`] fails
@@ -124,30 +185,6 @@ impl Terminal {
         Ok(())
     }
 
-    /// # Errors
-    ///
-    /// Will return `Err` if [`Terminal::move_cursor_to`], [`Terminal::clear_line`] or [`Terminal::print`] fail
-    pub fn print_row(at_row: usize, line: &str) -> Result<(), Error> {
-        Self::move_cursor_to(Position {
-            row: at_row,
-            col: 0,
-        })?;
-        Self::clear_line()?;
-        Self::print(line)?;
-        Ok(())
-    }
-
-    /// # Errors
-    ///
-    /// Will return `Err` if [`Terminal::set_color`] fails
-    pub fn set_color_to_green() -> Result<(), Error> {
-        Self::set_color(Colors {
-            foreground: Option::from(Color::Green),
-            background: None,
-        })?;
-        Ok(())
-    }
-
     /// Move cursor to the given Position.
     /// # Arguments
     /// * `Position` - the position the cursor will be moved to.
@@ -173,6 +210,25 @@ impl Terminal {
         Ok(())
     }
 
+    /// Sets the colors and attributes used by the next prints.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if [`crossterm::style::SetAttribute`] or [`Terminal::set_color`] fail
+    pub fn set_style(style: Style) -> Result<(), Error> {
+        let reverse = if style.reverse {
+            Attribute::Reverse
+        } else {
+            Attribute::NoReverse
+        };
+        Self::queue_command(crossterm::style::SetAttribute(reverse))?;
+        Self::set_color(Colors {
+            foreground: Some(style.foreground.unwrap_or(Color::Reset)),
+            background: Some(style.background.unwrap_or(Color::Reset)),
+        })?;
+        Ok(())
+    }
+
     /// # Errors
     ///
     /// Will return `Err` if [`crossterm::style::ResetColor`] fails
@@ -197,6 +253,19 @@ impl Terminal {
         Ok(())
     }
 
+    /// # Errors
+    ///
+    /// Will return `Err` if [`crossterm::cursor::SetCursorStyle`] fails
+    pub fn set_cursor_shape(shape: CursorShape) -> Result<(), Error> {
+        let style = match shape {
+            CursorShape::Default => SetCursorStyle::DefaultUserShape,
+            CursorShape::Block => SetCursorStyle::SteadyBlock,
+            CursorShape::Bar => SetCursorStyle::SteadyBar,
+        };
+        Self::queue_command(style)?;
+        Ok(())
+    }
+
     fn queue_command<T: Command>(command: T) -> Result<(), Error> {
         queue!(stdout(), command)?;
         Ok(())
@@ -210,3 +279,61 @@ impl Terminal {
         Ok(())
     }
 }
+
+impl Backend for Terminal {
+    fn set_up(&mut self) -> Result<(), Error> {
+        Self::set_up()
+    }
+
+    fn terminate(&mut self) -> Result<(), Error> {
+        Self::terminate()
+    }
+
+    fn suspend(&mut self) -> Result<(), Error> {
+        Self::suspend()
+    }
+
+    fn size(&self) -> Result<Size, Error> {
+        Self::get_size()
+    }
+
+    fn clear_screen(&mut self) -> Result<(), Error> {
+        Self::clear_screen()
+    }
+
+    fn move_cursor_to(&mut self, position: Position) -> Result<(), Error> {
+        Self::move_cursor_to(position)
+    }
+
+    fn set_style(&mut self, style: Style) -> Result<(), Error> {
+        Self::set_style(style)
+    }
+
+    fn print(&mut self, string: &str) -> Result<(), Error> {
+        Self::print(string)
+    }
+
+    fn hide_cursor(&mut self) -> Result<(), Error> {
+        Self::hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> Result<(), Error> {
+        Self::show_cursor()
+    }
+
+    fn set_cursor_shape(&mut self, shape: CursorShape) -> Result<(), Error> {
+        Self::set_cursor_shape(shape)
+    }
+
+    fn execute(&mut self) -> Result<(), Error> {
+        Self::execute()
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> Result<Option<Event>, Error> {
+        if poll(timeout)? {
+            Ok(Some(read()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
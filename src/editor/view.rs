@@ -1,28 +1,112 @@
-use crate::editor::editorcommand::{Direction, EditorCommand};
-use crate::editor::terminal::{Position, Size, Terminal};
+use crate::editor::documentstatus::DocumentStatus;
+use crate::editor::editorcommand::{Direction, EditorCommand, GoToTarget, LineTarget};
+use crate::editor::screen::{Screen, Style};
+use crate::editor::terminal::{Position, Size};
+use crate::editor::view::block::Block;
+use crate::editor::view::buffer::follower::Growth;
 use crate::editor::view::buffer::Buffer;
+use crate::editor::view::filter::{Filter, Pattern};
+use crate::editor::view::hex::HexCaret;
 use crate::editor::view::line::Line;
-use std::cmp::{min, PartialEq};
+use crossterm::style::Color;
+use std::cmp::{max, min, Ordering, PartialEq};
+use std::io::{Error, ErrorKind, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
+mod block;
 mod buffer;
+mod filter;
+mod hex;
 mod line;
 
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Location {
     pub grapheme_index: usize,
     pub line_index: usize,
 }
 
+/// Locations are ordered as they come in the text
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.line_index, self.grapheme_index).cmp(&(other.line_index, other.grapheme_index))
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// How many lines or columns a turn of the mouse wheel scrolls
+const SCROLL_STEP: usize = 3;
+
+/// How many copied or cut texts are kept
+const KILL_RING_SIZE: usize = 60;
+
+/// A text that was copied or cut
+#[derive(Default, Clone)]
+struct Clipboard {
+    text: String,
+    /// Whole lines are pasted above or below the current line instead of at the caret
+    is_linewise: bool,
+    /// The text of a block selection, its lines are pasted in the same column on the lines from the caret
+    is_block: bool,
+}
+
+/// A caret added besides the main one, with the other end of its selection
+#[derive(Clone, Debug, PartialEq)]
+struct Caret {
+    location: Location,
+    anchor: Option<Location>,
+}
+
+/// The commands that change what the next one does
+enum PreviousCommand {
+    KillLine,
+    /// The text pasted from the entry `index` of the kill ring, between `start` and `end`
+    Paste {
+        start: Location,
+        end: Location,
+        index: usize,
+    },
+}
+
 pub struct View {
     buffer: Buffer,
     needs_redraw: bool,
     size: Size,
     text_location: Location,
     scroll_offset: Position,
+    /// The other end of the selection, the caret being the first one
+    selection_anchor: Option<Location>,
+    /// The selection is made of whole lines, whatever the columns of its ends
+    is_selection_linewise: bool,
+    /// The carets added besides the main one, typing and moving happen at each of them
+    other_carets: Vec<Caret>,
+    /// The rectangular selection, the other selections are not used while there is one
+    block: Option<Block>,
+    /// The copied and cut texts, the last one is pasted
+    kill_ring: Vec<Clipboard>,
+    previous_command: Option<PreviousCommand>,
+    /// Where to put the caret once the file is loaded
+    pending_go_to: Option<GoToTarget>,
+    /// Where the caret was when the matching brackets were last highlighted
+    brackets_caret: Option<Location>,
+    /// What the last command has to tell, like why it was refused
+    message: Option<String>,
+    /// The full buffer while only the lines matching a pattern are shown, see [`View::filter`]
+    filter: Option<Filter>,
+    /// The caret while the view shows the bytes of a binary buffer, see [`View::toggle_hex`]
+    hex_caret: HexCaret,
 }
 
 impl View {
-    pub fn render(&mut self) {
+    pub fn render(&mut self, screen: &mut Screen) {
+        if self.brackets_caret.as_ref() != Some(&self.text_location) {
+            self.needs_redraw = true;
+        }
         if !self.needs_redraw {
             return;
         }
@@ -30,30 +114,128 @@ impl View {
         if width == 0 || height == 0 {
             return;
         }
+        if self.buffer.is_binary() {
+            self.render_hex(screen);
+            return;
+        }
         //We allow this because it doesn't matter is the version is exactly at 1/3 of the screen
         #[allow(clippy::integer_division)]
         let vertical_center = height / 3;
         let top = self.scroll_offset.row;
+        self.buffer
+            .lines
+            .release(&(top..top.saturating_add(height)));
+        let brackets = self.buffer.highlighted_bracket_pair(&self.text_location);
+        self.brackets_caret = Some(self.text_location.clone());
+        let gutter_width = self.gutter_width();
+        let left = self.scroll_offset.col;
+        // The columns of the text are shifted right by the real line numbers of a filtered view
+        let to_screen = |col: usize| col.saturating_sub(left).saturating_add(gutter_width);
 
         for current_row in 0..height {
-            if let Some(line) = self.buffer.lines.get(current_row.saturating_add(top)) {
-                let left = self.scroll_offset.col;
-                let right = self.scroll_offset.col.saturating_add(width);
-                Self::render_line(current_row, &line.get_visible_graphemes(left..right));
-            } else if current_row == vertical_center && self.buffer.is_empty() {
-                Self::render_line(current_row, &Self::build_welcome_message(width));
+            let line_index = current_row.saturating_add(top);
+            if let Some(line) = self.buffer.lines.get(line_index) {
+                let right = left.saturating_add(self.text_width());
+                let visible = line.get_visible_graphemes(left..right);
+                match self
+                    .filter
+                    .as_ref()
+                    .and_then(|filter| filter.line_index(line_index))
+                {
+                    Some(real_index) => {
+                        let number = real_index.saturating_add(1);
+                        let number_width = gutter_width.saturating_sub(1);
+                        screen
+                            .print_row(current_row, &format!("{number:>number_width$} {visible}"));
+                        screen.set_style(
+                            current_row,
+                            0..number_width,
+                            Style::foreground(Color::DarkGrey),
+                        );
+                    }
+                    None => screen.print_row(current_row, &visible),
+                }
+                for bracket in brackets.iter().flat_map(|(first, second)| [first, second]) {
+                    if bracket.line_index == line_index {
+                        let col = line.width_until(bracket.grapheme_index);
+                        if col >= left {
+                            let col = to_screen(col);
+                            screen.set_style(
+                                current_row,
+                                col..col.saturating_add(1),
+                                Style::background(Color::DarkCyan),
+                            );
+                        }
+                    }
+                }
+                for selection in self.selection_ranges() {
+                    if let Some(selected) = self.selected_columns(&selection, line_index) {
+                        let visible = to_screen(selected.start)..to_screen(selected.end);
+                        screen.set_style(current_row, visible, Style::reversed());
+                    }
+                }
+                if let Some(block) = self
+                    .block
+                    .filter(|block| block.line_indexes().contains(&line_index))
+                {
+                    let columns = block.highlighted_columns(line);
+                    screen.set_style(
+                        current_row,
+                        to_screen(columns.start)..to_screen(columns.end),
+                        Style::reversed(),
+                    );
+                }
+                for caret in &self.other_carets {
+                    let col = line.width_until(caret.location.grapheme_index);
+                    if caret.location.line_index == line_index && col >= left {
+                        let col = to_screen(col);
+                        screen.set_style(
+                            current_row,
+                            col..col.saturating_add(1),
+                            Style::reversed(),
+                        );
+                    }
+                }
+            } else if current_row == vertical_center
+                && self.buffer.is_empty()
+                && !self.buffer.is_loading()
+            {
+                screen.print_row(current_row, &Self::build_welcome_message(width));
             } else {
-                let _ = Terminal::set_color_to_green();
-                Self::render_line(current_row, "~");
+                screen.print_styled_row(current_row, "~", Style::foreground(Color::Green));
             }
         }
-        let _ = Terminal::reset_color();
         self.needs_redraw = false;
     }
 
-    fn render_line(at: usize, line_text: &str) {
-        let result = Terminal::print_row(at, line_text);
-        debug_assert!(result.is_ok(), "Failed to render line");
+    /// Draws a row of offset, hex digits and characters for every 16 bytes, the character under the caret reversed
+    fn render_hex(&mut self, screen: &mut Screen) {
+        let bytes = self.buffer.bytes().unwrap_or_default();
+        let Position {
+            row: top,
+            col: left,
+        } = self.scroll_offset;
+        let caret_row = self.hex_caret.position().row;
+        let to_screen = |col: usize| col.saturating_sub(left);
+        for current_row in 0..self.size.height {
+            let row = current_row.saturating_add(top);
+            let Some(text) = hex::format_row(bytes, row) else {
+                screen.print_styled_row(current_row, "~", Style::foreground(Color::Green));
+                continue;
+            };
+            screen.print_row(current_row, text.get(left..).unwrap_or_default());
+            screen.set_style(
+                current_row,
+                0..to_screen(hex::OFFSET_WIDTH),
+                Style::foreground(Color::DarkGrey),
+            );
+            let col = self.hex_caret.text_column();
+            if row == caret_row && col >= left {
+                let col = to_screen(col);
+                screen.set_style(current_row, col..col.saturating_add(1), Style::reversed());
+            }
+        }
+        self.needs_redraw = false;
     }
 
     fn build_welcome_message(width: usize) -> String {
@@ -73,31 +255,1164 @@ impl View {
         version
     }
 
+    /// Returns the columns of `selection` on the line `line_index`, the end of line counting as one column
+    fn selected_columns(
+        &self,
+        (start, end): &(Location, Location),
+        line_index: usize,
+    ) -> Option<Range<usize>> {
+        if line_index < start.line_index || line_index > end.line_index {
+            return None;
+        }
+        let line = self.buffer.lines.get(line_index)?;
+        let from = if line_index == start.line_index {
+            line.width_until(start.grapheme_index)
+        } else {
+            0
+        };
+        let to = if line_index == end.line_index {
+            line.width_until(end.grapheme_index)
+        } else {
+            line.width_until(line.grapheme_count()).saturating_add(1)
+        };
+        Some(from..to)
+    }
+
     pub fn handle_command(&mut self, command: EditorCommand) {
+        let gutter_width = self.gutter_width();
+        let text_position = |position: Position| Position {
+            col: position.col.saturating_sub(gutter_width),
+            row: position.row,
+        };
+        // The mouse positions are on the screen, the real line numbers of a filtered view come before the text
+        let command = match command {
+            EditorCommand::Click(position) => EditorCommand::Click(text_position(position)),
+            EditorCommand::DoubleClick(position) => {
+                EditorCommand::DoubleClick(text_position(position))
+            }
+            EditorCommand::Drag(position) => EditorCommand::Drag(text_position(position)),
+            EditorCommand::ToggleCaret(position) => {
+                EditorCommand::ToggleCaret(text_position(position))
+            }
+            EditorCommand::DragBlock(position) => EditorCommand::DragBlock(text_position(position)),
+            command => command,
+        };
+        self.run_command(command);
+        if let Some(filter) = &mut self.filter {
+            filter.sync(&self.buffer);
+        }
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn run_command(&mut self, command: EditorCommand) {
+        if self.refuses(&command)
+            || self.handle_command_in_hex(&command)
+            || self.handle_command_in_block(&command)
+            || self.handle_command_at_carets(&command)
+        {
+            return;
+        }
+        let previous_command = self.previous_command.take();
         match command {
             EditorCommand::Resize(size) => self.resize(size),
             EditorCommand::Move(direction) => {
-                self.move_text_location(&direction);
+                self.clear_selection();
+                self.move_text_location(direction);
             }
             EditorCommand::Insert('\t') => {
+                self.delete_selection();
                 for _ in 0..4 {
                     self.insert_char(' ');
                 }
             }
             EditorCommand::Insert(char) => {
+                self.delete_selection();
                 self.insert_char(char);
             }
             EditorCommand::Enter => {
+                self.delete_selection();
                 self.insert_line();
             }
-            EditorCommand::Delete => {
-                self.delete();
+            EditorCommand::Delete
+            | EditorCommand::Backspace
+            | EditorCommand::DeleteWordBackward
+            | EditorCommand::DeleteWordForward => {
+                if !self.delete_selection() {
+                    self.delete_at_caret(&command);
+                }
             }
-            EditorCommand::Backspace => self.backspace(),
-            EditorCommand::Quit => {}
+            EditorCommand::Click(position) => self.click(position),
+            EditorCommand::DoubleClick(position) => self.select_word(position),
+            EditorCommand::Drag(position) => self.drag(position),
+            EditorCommand::Scroll(direction) => self.scroll(direction),
+            EditorCommand::JumpToMatchingBracket => {
+                self.clear_selection();
+                self.jump_to_matching_bracket();
+            }
+            EditorCommand::Select(direction) => self.select(direction),
+            EditorCommand::SelectInLine(direction) => self.select_in_line(direction),
+            EditorCommand::StartSelection => self.start_selection(false),
+            EditorCommand::StartLineSelection => self.start_selection(true),
+            EditorCommand::ClearSelection => self.clear_selection(),
+            EditorCommand::CollapseSelection => self.collapse_selection(),
+            EditorCommand::Copy => self.copy(),
+            EditorCommand::Cut => {
+                self.copy();
+                self.delete_selection();
+            }
+            EditorCommand::Paste | EditorCommand::PasteAfter => {
+                self.paste(command == EditorCommand::PasteAfter);
+            }
+            EditorCommand::KillLine => {
+                self.clear_selection();
+                self.kill_line(matches!(previous_command, Some(PreviousCommand::KillLine)));
+            }
+            EditorCommand::YankPop => {
+                if let Some(PreviousCommand::Paste { start, end, index }) = previous_command {
+                    self.yank_pop(start, &end, index);
+                }
+            }
+            EditorCommand::OpenLineBelow | EditorCommand::OpenLineAbove => {
+                self.clear_selection();
+                self.open_line(command == EditorCommand::OpenLineAbove);
+            }
+            EditorCommand::GoTo(target) => {
+                self.clear_selection();
+                self.go_to(target);
+            }
+            EditorCommand::SelectBlock(direction) => self.select_block(direction),
+            EditorCommand::DragBlock(position) => self.drag_block(position),
+            EditorCommand::ToggleCaret(position) => self.toggle_caret(position),
+            EditorCommand::AddCaretAbove => self.add_caret_on_next_line(false),
+            EditorCommand::AddCaretBelow => self.add_caret_on_next_line(true),
+            EditorCommand::AddNextOccurrence => self.add_next_occurrence(),
+            EditorCommand::ToggleReadOnly => {
+                self.buffer.toggle_read_only();
+                if let Some(filter) = &mut self.filter {
+                    filter.buffer.toggle_read_only();
+                }
+            }
+            EditorCommand::ToggleFollow => self.toggle_follow(),
+            EditorCommand::ToggleHex => self.toggle_hex(),
+            EditorCommand::Quit
+            | EditorCommand::NextBuffer
+            | EditorCommand::PreviousBuffer
+            | EditorCommand::PromptGoToLine
+            | EditorCommand::PromptFilter
+            | EditorCommand::Save
+            | EditorCommand::Suspend
+            | EditorCommand::StartRecording(_)
+            | EditorCommand::StopRecording
+            | EditorCommand::PlayMacro { .. } => {}
+        }
+    }
+
+    /// Runs the commands of a view showing bytes, returns `false` for the ones that do not depend on the text.
+    /// The caret moves by bytes and typed hex digits overwrite the ones under it, the other edits are refused.
+    fn handle_command_in_hex(&mut self, command: &EditorCommand) -> bool {
+        let Some(len) = self.buffer.bytes().map(<[u8]>::len) else {
+            return false;
+        };
+        match command {
+            EditorCommand::Move(direction)
+            | EditorCommand::Select(direction)
+            | EditorCommand::SelectInLine(direction) => {
+                self.hex_caret = self.hex_caret.moved(*direction, len, self.size.height);
+            }
+            EditorCommand::Insert(character) => self.overwrite_hex_digit(*character),
+            EditorCommand::Click(position) => {
+                let position = position.saturating_add(self.scroll_offset);
+                if let Some(caret) = HexCaret::from_position(position) {
+                    self.hex_caret = caret.clamped(len);
+                }
+            }
+            EditorCommand::Scroll(direction @ (Direction::Up | Direction::Down)) => {
+                let row = self.scroll_offset.row;
+                self.scroll_offset.row = if *direction == Direction::Up {
+                    row.saturating_sub(SCROLL_STEP)
+                } else {
+                    min(
+                        row.saturating_add(SCROLL_STEP),
+                        hex::row_count(len).saturating_sub(1),
+                    )
+                };
+                self.needs_redraw = true;
+                return true;
+            }
+            EditorCommand::Resize(_)
+            | EditorCommand::Scroll(_)
+            | EditorCommand::ToggleReadOnly
+            | EditorCommand::ToggleFollow
+            | EditorCommand::ToggleHex => return false,
+            _ if command.edits_text() => {
+                self.message = Some("Bytes can only be overwritten with hex digits".to_string());
+                return true;
+            }
+            _ => return true,
+        }
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+        true
+    }
+
+    /// Replaces the hex digit under the caret by the one typed, then moves to the next digit
+    fn overwrite_hex_digit(&mut self, character: char) {
+        let Some(digit) = character
+            .to_digit(16)
+            .and_then(|digit| u8::try_from(digit).ok())
+        else {
+            self.message = Some("Type a hex digit, from 0 to 9 or a to f".to_string());
+            return;
+        };
+        let caret = self.hex_caret;
+        let Some(byte) = self
+            .buffer
+            .bytes()
+            .and_then(|bytes| bytes.get(caret.offset))
+            .copied()
+        else {
+            return;
+        };
+        if self
+            .buffer
+            .set_byte(caret.offset, caret.overwrite(byte, digit))
+        {
+            let len = self.buffer.bytes().map_or(0, <[u8]>::len);
+            self.hex_caret = caret.next_digit(len);
+        }
+    }
+
+    /// Runs the commands that edit the block selection when there is one, returns `true` if `command` was one of them.
+    /// The block selection is removed before the other commands, except the ones that move it or the view.
+    fn handle_command_in_block(&mut self, command: &EditorCommand) -> bool {
+        let Some(block) = self.block else {
+            return false;
+        };
+        match command {
+            EditorCommand::Insert('\t') => self.insert_in_block("    "),
+            EditorCommand::Insert(character) => self.insert_in_block(&character.to_string()),
+            EditorCommand::Backspace => self.delete_in_block(false),
+            EditorCommand::Delete => self.delete_in_block(true),
+            EditorCommand::Copy => self.copy_block(),
+            EditorCommand::Cut => {
+                self.copy_block();
+                self.delete_block_contents();
+            }
+            EditorCommand::Paste | EditorCommand::PasteAfter => {
+                let Some(clipboard) = self.kill_ring.last().cloned() else {
+                    return true;
+                };
+                if clipboard.is_block {
+                    self.delete_block_contents();
+                    self.block = None;
+                    let line_index = *block.line_indexes().start();
+                    self.paste_block(&clipboard.text, block.columns().start, line_index);
+                } else if clipboard.text.contains('\n') {
+                    // Several lines can not be pasted in each line of the block
+                    self.block = None;
+                    self.needs_redraw = true;
+                    return false;
+                } else {
+                    self.insert_in_block(&clipboard.text);
+                }
+            }
+            EditorCommand::SelectBlock(_)
+            | EditorCommand::DragBlock(_)
+            | EditorCommand::Resize(_)
+            | EditorCommand::Scroll(_) => return false,
+            _ => {
+                self.block = None;
+                self.needs_redraw = true;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Sets the block selection and puts the caret at the grapheme drawn in its `caret` corner, or at the end of the line
+    fn set_block(&mut self, block: Block) {
+        self.text_location = Location {
+            grapheme_index: self
+                .buffer
+                .lines
+                .get(block.caret.row)
+                .map_or(0, |line| line.grapheme_index_from(block.caret.col)),
+            line_index: block.caret.row,
+        };
+        self.block = Some(block);
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    /// Moves the `caret` corner of the block selection, which starts at the caret if there is none.
+    /// Moving left or right goes over whole full-width graphemes, and can go past the end of the line.
+    fn select_block(&mut self, direction: Direction) {
+        let mut block = self.block.unwrap_or_else(|| {
+            let corner = self.text_location_to_position();
+            Block {
+                anchor: corner,
+                caret: corner,
+            }
+        });
+        self.clear_selection();
+        let Position { col, row } = block.caret;
+        let line = self.buffer.lines.get(row);
+        match direction {
+            Direction::Up => block.caret.row = row.saturating_sub(1),
+            Direction::Down => {
+                block.caret.row = min(
+                    row.saturating_add(1),
+                    self.buffer.height().saturating_sub(1),
+                );
+            }
+            Direction::Left => {
+                block.caret.col = line
+                    .map(|line| (line, line.grapheme_index_from(col)))
+                    .filter(|(line, index)| *index > 0 && line.width_until(*index) == col)
+                    .map_or(col.saturating_sub(1), |(line, index)| {
+                        line.width_until(index.saturating_sub(1))
+                    });
+            }
+            Direction::Right => {
+                block.caret.col = line
+                    .map(|line| (line, line.grapheme_index_at(col)))
+                    .filter(|(line, index)| *index < line.grapheme_count())
+                    .map_or(col.saturating_add(1), |(line, index)| {
+                        line.width_until(index.saturating_add(1))
+                    });
+            }
+            Direction::Home => block.caret.col = 0,
+            Direction::End => {
+                block.caret.col = line.map_or(0, |line| line.width_until(line.grapheme_count()));
+            }
+            _ => {}
+        }
+        self.set_block(block);
+    }
+
+    /// Extends the block selection from the caret, or from where the drag started, to `position` on the screen
+    fn drag_block(&mut self, position: Position) {
+        let anchor = self
+            .block
+            .map_or_else(|| self.text_location_to_position(), |block| block.anchor);
+        self.clear_selection();
+        let caret = Position {
+            col: position.col.saturating_add(self.scroll_offset.col),
+            row: min(
+                position.row.saturating_add(self.scroll_offset.row),
+                self.buffer.height().saturating_sub(1),
+            ),
+        };
+        self.set_block(Block { anchor, caret });
+    }
+
+    /// Copies the text of the block selection, with a line of text for each of its lines
+    fn copy_block(&mut self) {
+        let Some(block) = self.block else {
+            return;
+        };
+        let lines: Vec<String> = block
+            .line_indexes()
+            .map(|line_index| {
+                self.buffer
+                    .lines
+                    .get(line_index)
+                    .map(|line| {
+                        let graphemes = block.graphemes(line);
+                        line.graphemes()
+                            .skip(graphemes.start)
+                            .take(graphemes.len())
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        self.push_kill(Clipboard {
+            text: lines.join("\n"),
+            is_linewise: false,
+            is_block: true,
+        });
+    }
+
+    /// Deletes the text of the block selection on each of its lines, the block is then empty at its left side
+    fn delete_block_contents(&mut self) {
+        let Some(mut block) = self.block else {
+            return;
+        };
+        if self.buffer.is_read_only() {
+            return;
+        }
+        for line_index in block.line_indexes() {
+            let Some(graphemes) = self
+                .buffer
+                .lines
+                .get(line_index)
+                .map(|line| block.graphemes(line))
+            else {
+                continue;
+            };
+            self.buffer.delete_range(
+                &Location {
+                    grapheme_index: graphemes.start,
+                    line_index,
+                },
+                &Location {
+                    grapheme_index: graphemes.end,
+                    line_index,
+                },
+            );
+        }
+        let col = block.columns().start;
+        block.anchor.col = col;
+        block.caret.col = col;
+        self.set_block(block);
+    }
+
+    /// Deletes the text of the block selection or, if it is empty, the grapheme before it or after it if `forward`,
+    /// on each of its lines
+    fn delete_in_block(&mut self, forward: bool) {
+        let Some(mut block) = self.block else {
+            return;
+        };
+        if self.buffer.is_read_only() {
+            return;
+        }
+        if !block.is_empty() {
+            self.delete_block_contents();
+            return;
+        }
+        let col = block.columns().start;
+        let mut new_col = None;
+        for line_index in block.line_indexes() {
+            let Some(line) = self.buffer.lines.get(line_index) else {
+                continue;
+            };
+            let index = block.graphemes(line).start;
+            // Lines too short to reach the block have nothing to delete
+            if line.width_until(index) < col {
+                continue;
+            }
+            let start = if forward {
+                index
+            } else {
+                let Some(previous) = index.checked_sub(1) else {
+                    continue;
+                };
+                new_col = Some(min(new_col.unwrap_or(col), line.width_until(previous)));
+                previous
+            };
+            if start >= line.grapheme_count() {
+                continue;
+            }
+            self.buffer.delete_range(
+                &Location {
+                    grapheme_index: start,
+                    line_index,
+                },
+                &Location {
+                    grapheme_index: start.saturating_add(1),
+                    line_index,
+                },
+            );
+        }
+        if !forward {
+            let col = new_col.unwrap_or_else(|| col.saturating_sub(1));
+            block.anchor.col = col;
+            block.caret.col = col;
+        }
+        self.set_block(block);
+    }
+
+    /// Types `text` on each line of the block selection in place of its text
+    fn insert_in_block(&mut self, text: &str) {
+        if self.buffer.is_read_only() {
+            return;
+        }
+        self.delete_block_contents();
+        let Some(mut block) = self.block else {
+            return;
+        };
+        let col = block.columns().start;
+        for line_index in block.line_indexes() {
+            self.insert_at_column(text, col, line_index);
+        }
+        let text = Line::from(text);
+        let col = col.saturating_add(text.width_until(text.grapheme_count()));
+        block.anchor.col = col;
+        block.caret.col = col;
+        self.set_block(block);
+    }
+
+    /// Pastes each line of `text` at the column `col` of the lines from `line_index`, and puts the caret after the first one
+    fn paste_block(&mut self, text: &str, col: usize, line_index: usize) {
+        if self.buffer.is_read_only() {
+            return;
+        }
+        let mut caret = None;
+        for (offset, line_text) in text.split('\n').enumerate() {
+            let end = self.insert_at_column(line_text, col, line_index.saturating_add(offset));
+            caret = caret.or(end);
+        }
+        if let Some(caret) = caret {
+            self.text_location = caret;
+        }
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    /// Inserts `text` at the column `col` of the line `line_index`. Spaces are added to a line too short to reach `col`,
+    /// and empty lines after the last one if needed. Returns where the inserted text ends.
+    fn insert_at_column(&mut self, text: &str, col: usize, line_index: usize) -> Option<Location> {
+        while self.buffer.height() <= line_index {
+            let end = Location {
+                grapheme_index: 0,
+                line_index: self.buffer.height(),
+            };
+            self.buffer.insert_text(&end, "");
+        }
+        let line = self.buffer.lines.get(line_index)?;
+        let padding = " ".repeat(col.saturating_sub(line.width_until(line.grapheme_count())));
+        let at = Location {
+            grapheme_index: line.grapheme_index_from(col),
+            line_index,
+        };
+        Some(self.buffer.insert_text(&at, &format!("{padding}{text}")))
+    }
+
+    /// Runs the commands that apply to every caret when there are several, returns `true` if `command` was one of them.
+    /// The other carets are removed before the commands that only make sense at one place.
+    fn handle_command_at_carets(&mut self, command: &EditorCommand) -> bool {
+        if self.other_carets.is_empty() {
+            return false;
+        }
+        match command {
+            EditorCommand::Move(_)
+            | EditorCommand::Select(_)
+            | EditorCommand::SelectInLine(_)
+            | EditorCommand::Insert(_)
+            | EditorCommand::Enter
+            | EditorCommand::Delete
+            | EditorCommand::Backspace
+            | EditorCommand::DeleteWordBackward
+            | EditorCommand::DeleteWordForward
+            | EditorCommand::StartSelection
+            | EditorCommand::StartLineSelection
+            | EditorCommand::CollapseSelection => {
+                self.for_each_caret(|view| view.handle_command(command.clone()));
+                return true;
+            }
+            EditorCommand::Resize(_)
+            | EditorCommand::Scroll(_)
+            | EditorCommand::Copy
+            | EditorCommand::SelectBlock(_)
+            | EditorCommand::DragBlock(_)
+            | EditorCommand::ToggleCaret(_)
+            | EditorCommand::AddCaretAbove
+            | EditorCommand::AddCaretBelow
+            | EditorCommand::AddNextOccurrence
+            | EditorCommand::ToggleReadOnly => {}
+            _ => {
+                self.other_carets.clear();
+                self.needs_redraw = true;
+            }
+        }
+        false
+    }
+
+    /// Returns the selection, start first, or `None` if nothing is selected.
+    /// A selection of lines goes from the start of its first line to the start of the line after the last one,
+    /// or the end of the last line of the buffer.
+    fn selection_range(&self) -> Option<(Location, Location)> {
+        let anchor = self.selection_anchor.clone()?;
+        self.range_between(anchor, self.text_location.clone())
+    }
+
+    /// Returns the selection of every caret, the one of the main caret first
+    fn selection_ranges(&self) -> Vec<(Location, Location)> {
+        let others = self
+            .other_carets
+            .iter()
+            .filter_map(|caret| self.range_between(caret.anchor.clone()?, caret.location.clone()));
+        self.selection_range().into_iter().chain(others).collect()
+    }
+
+    /// Returns the selection between `anchor` and `caret`, see [`View::selection_range`]
+    fn range_between(&self, anchor: Location, caret: Location) -> Option<(Location, Location)> {
+        let (start, end) = if (anchor.line_index, anchor.grapheme_index)
+            < (caret.line_index, caret.grapheme_index)
+        {
+            (anchor, caret)
+        } else {
+            (caret, anchor)
+        };
+        if self.is_selection_linewise {
+            let next_line_index = end.line_index.saturating_add(1);
+            let end = if next_line_index < self.buffer.height() {
+                Location {
+                    grapheme_index: 0,
+                    line_index: next_line_index,
+                }
+            } else {
+                Location {
+                    grapheme_index: self
+                        .buffer
+                        .lines
+                        .get(end.line_index)
+                        .map_or(0, Line::grapheme_count),
+                    line_index: end.line_index,
+                }
+            };
+            return Some((
+                Location {
+                    grapheme_index: 0,
+                    line_index: start.line_index,
+                },
+                end,
+            ));
+        }
+        if start == end {
+            None
+        } else {
+            Some((start, end))
+        }
+    }
+
+    fn start_selection(&mut self, is_linewise: bool) {
+        self.selection_anchor = Some(self.text_location.clone());
+        self.is_selection_linewise = is_linewise;
+        self.needs_redraw = true;
+    }
+
+    /// Moves the caret, the selection going from where the caret was if there is none yet
+    fn select(&mut self, direction: Direction) {
+        if self.selection_anchor.is_none() {
+            self.start_selection(false);
+        }
+        self.move_text_location(direction);
+        self.needs_redraw = true;
+    }
+
+    /// Selects like [`View::select`], unless the caret would leave its line
+    fn select_in_line(&mut self, direction: Direction) {
+        let location = self.text_location.clone();
+        self.select(direction);
+        if self.text_location.line_index != location.line_index {
+            self.text_location = location;
+        }
+    }
+
+    fn clear_selection(&mut self) {
+        self.is_selection_linewise = false;
+        if self.selection_anchor.take().is_some() {
+            self.needs_redraw = true;
+        }
+    }
+
+    fn collapse_selection(&mut self) {
+        if let Some((start, _)) = self.selection_range() {
+            self.text_location = start;
+            self.scroll_text_location_into_view();
+        }
+        self.clear_selection();
+    }
+
+    fn copy(&mut self) {
+        let Some((start, end)) = self.selection_range() else {
+            return;
+        };
+        let mut text = self.buffer.text_in_range(&start, &end);
+        if self.is_selection_linewise && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        self.push_kill(Clipboard {
+            text,
+            is_linewise: self.is_selection_linewise,
+            is_block: false,
+        });
+    }
+
+    fn push_kill(&mut self, clipboard: Clipboard) {
+        if self.kill_ring.len() >= KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+        self.kill_ring.push(clipboard);
+    }
+
+    /// Cuts the rest of the line, or the line break if the caret is at the end of the line.
+    /// If `append`, the text is added to the last entry of the kill ring instead of making a new one.
+    fn kill_line(&mut self, append: bool) {
+        if self.buffer.is_read_only() {
+            return;
+        }
+        let start = self.text_location.clone();
+        let line_len = self
+            .buffer
+            .lines
+            .get(start.line_index)
+            .map_or(0, Line::grapheme_count);
+        let end = if start.grapheme_index < line_len {
+            Location {
+                grapheme_index: line_len,
+                line_index: start.line_index,
+            }
+        } else if start.line_index.saturating_add(1) < self.buffer.height() {
+            Location {
+                grapheme_index: 0,
+                line_index: start.line_index.saturating_add(1),
+            }
+        } else {
+            return;
+        };
+        let text = self.buffer.text_in_range(&start, &end);
+        match self.kill_ring.last_mut() {
+            Some(last) if append => last.text.push_str(&text),
+            _ => self.push_kill(Clipboard {
+                text,
+                is_linewise: false,
+                is_block: false,
+            }),
+        }
+        self.buffer.delete_range(&start, &end);
+        self.previous_command = Some(PreviousCommand::KillLine);
+        self.needs_redraw = true;
+    }
+
+    /// Replaces the text pasted between `start` and `end` from the entry `index` of the kill ring by the entry before it,
+    /// going back to the last one after the first
+    fn yank_pop(&mut self, start: Location, end: &Location, index: usize) {
+        if self.buffer.is_read_only() || self.kill_ring.is_empty() {
+            return;
+        }
+        let index = index
+            .checked_sub(1)
+            .unwrap_or_else(|| self.kill_ring.len().saturating_sub(1));
+        let Some(Clipboard { text, .. }) = self.kill_ring.get(index).cloned() else {
+            return;
+        };
+        self.buffer.delete_range(&start, end);
+        let end = self.buffer.insert_text(&start, &text);
+        self.text_location = end.clone();
+        self.previous_command = Some(PreviousCommand::Paste { start, end, index });
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    /// Inserts the last entry of the kill ring at the caret, or after the grapheme under the caret if `after`.
+    /// Whole lines are inserted above the current line, or below it if `after`.
+    fn paste(&mut self, after: bool) {
+        if self.buffer.is_read_only() {
+            return;
+        }
+        self.delete_selection();
+        let index = self.kill_ring.len().saturating_sub(1);
+        let Some(Clipboard {
+            text,
+            is_linewise,
+            is_block,
+        }) = self.kill_ring.get(index).cloned()
+        else {
+            return;
+        };
+        if text.is_empty() {
+            return;
+        }
+        if is_block {
+            let Position { col, row } = self.text_location_to_position();
+            self.paste_block(&text, col, row);
+            return;
+        }
+        let Location {
+            grapheme_index,
+            line_index,
+        } = self.text_location;
+        let line_len = self
+            .buffer
+            .lines
+            .get(line_index)
+            .map_or(0, Line::grapheme_count);
+        let next_line_index = line_index.saturating_add(1);
+        if is_linewise {
+            let (at, text) = match (after, next_line_index < self.buffer.height()) {
+                (false, _) => ((0, line_index), text),
+                (true, true) => ((0, next_line_index), text),
+                // Below the last line, the line break goes before the text
+                (true, false) => (
+                    (line_len, line_index),
+                    format!("\n{}", text.strip_suffix('\n').unwrap_or(&text)),
+                ),
+            };
+            let start = Location {
+                grapheme_index: at.0,
+                line_index: at.1,
+            };
+            let end = self.buffer.insert_text(&start, &text);
+            self.previous_command = Some(PreviousCommand::Paste { start, end, index });
+            self.text_location = Location {
+                grapheme_index: 0,
+                line_index: if after { next_line_index } else { line_index },
+            };
+        } else {
+            let at = Location {
+                grapheme_index: if after {
+                    min(grapheme_index.saturating_add(1), line_len)
+                } else {
+                    grapheme_index
+                },
+                line_index,
+            };
+            self.text_location = self.buffer.insert_text(&at, &text);
+            self.previous_command = Some(PreviousCommand::Paste {
+                start: at,
+                end: self.text_location.clone(),
+                index,
+            });
+        }
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    /// Deletes the selected text and puts the caret where it was.
+    /// Returns `false` if nothing was selected.
+    fn delete_selection(&mut self) -> bool {
+        let Some((mut start, end)) = self.selection_range() else {
+            return false;
+        };
+        if self.buffer.is_read_only() {
+            return true;
+        }
+        let last_selected_line = self
+            .selection_anchor
+            .as_ref()
+            .map_or(0, |anchor| anchor.line_index)
+            .max(self.text_location.line_index);
+        if self.is_selection_linewise
+            && last_selected_line.saturating_add(1) >= self.buffer.height()
+        {
+            // There is no line after the last one, the line break before the first one goes instead
+            if let Some(previous_line_index) = start.line_index.checked_sub(1) {
+                start = Location {
+                    grapheme_index: self
+                        .buffer
+                        .lines
+                        .get(previous_line_index)
+                        .map_or(0, Line::grapheme_count),
+                    line_index: previous_line_index,
+                };
+            }
+        }
+        self.clear_selection();
+        self.buffer.delete_range(&start, &end);
+        self.text_location = start;
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+        true
+    }
+
+    /// Runs `action` with each caret made the main one in turn.
+    /// Carets are handled from the last one in the text to the first one, so that an edit at a caret only changes
+    /// the text before the carets already handled. These are kept as distances to the end of the text meanwhile,
+    /// which such edits do not change. Carets that end up in the same place are merged afterwards.
+    fn for_each_caret(&mut self, mut action: impl FnMut(&mut Self)) {
+        let scroll_offset = self.scroll_offset;
+        let main = Caret {
+            location: self.text_location.clone(),
+            anchor: self.selection_anchor.take(),
+        };
+        let mut carets: Vec<(bool, Caret)> = std::iter::once((true, main))
+            .chain(
+                std::mem::take(&mut self.other_carets)
+                    .into_iter()
+                    .map(|caret| (false, caret)),
+            )
+            .collect();
+        carets.sort_by(|(_, first), (_, second)| second.location.cmp(&first.location));
+        let mut handled = Vec::with_capacity(carets.len());
+        for (is_main, caret) in carets {
+            self.text_location = caret.location;
+            self.selection_anchor = caret.anchor;
+            action(self);
+            let anchor = self
+                .selection_anchor
+                .take()
+                .map(|anchor| self.distance_to_end(&anchor));
+            handled.push((is_main, self.distance_to_end(&self.text_location), anchor));
+        }
+        for (is_main, distance, anchor) in handled {
+            let caret = Caret {
+                location: self.location_from_end(distance),
+                anchor: anchor.map(|anchor| self.location_from_end(anchor)),
+            };
+            if is_main {
+                self.text_location = caret.location;
+                self.selection_anchor = caret.anchor;
+            } else {
+                self.other_carets.push(caret);
+            }
+        }
+        self.merge_carets();
+        self.scroll_offset = scroll_offset;
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    /// Returns how many lines come after `location`, and how many graphemes come after it on its line
+    fn distance_to_end(&self, location: &Location) -> (usize, usize) {
+        let line_len = self
+            .buffer
+            .lines
+            .get(location.line_index)
+            .map_or(0, Line::grapheme_count);
+        (
+            self.buffer
+                .height()
+                .saturating_sub(1)
+                .saturating_sub(location.line_index),
+            line_len.saturating_sub(location.grapheme_index),
+        )
+    }
+
+    /// Returns the location at a distance from the end of the text given by [`View::distance_to_end`]
+    fn location_from_end(&self, (lines_after, graphemes_after): (usize, usize)) -> Location {
+        let line_index = self
+            .buffer
+            .height()
+            .saturating_sub(1)
+            .saturating_sub(lines_after);
+        let line_len = self
+            .buffer
+            .lines
+            .get(line_index)
+            .map_or(0, Line::grapheme_count);
+        Location {
+            grapheme_index: line_len.saturating_sub(graphemes_after),
+            line_index,
+        }
+    }
+
+    /// Removes the carets in the same place as another one, or in its selection. The main caret is always kept.
+    fn merge_carets(&mut self) {
+        let span = |location: &Location, anchor: Option<&Location>| {
+            let other = anchor.unwrap_or(location);
+            (min(location, other).clone(), max(location, other).clone())
+        };
+        let mut kept = vec![span(&self.text_location, self.selection_anchor.as_ref())];
+        let mut other_carets = Vec::new();
+        for caret in std::mem::take(&mut self.other_carets) {
+            let (start, end) = span(&caret.location, caret.anchor.as_ref());
+            let overlaps = kept
+                .iter()
+                .any(|(kept_start, kept_end)| start <= *kept_end && *kept_start <= end);
+            if !overlaps {
+                kept.push((start, end));
+                other_carets.push(caret);
+            }
+        }
+        self.other_carets = other_carets;
+    }
+
+    /// Makes `location` the main caret, the main caret becoming one of the others
+    fn push_main_caret(&mut self, location: Location) {
+        let caret = Caret {
+            location: std::mem::replace(&mut self.text_location, location),
+            anchor: self.selection_anchor.take(),
+        };
+        self.other_carets.push(caret);
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    /// Adds a caret at `position`, or removes the caret that is already there unless it is the only one
+    fn toggle_caret(&mut self, position: Position) {
+        if position.row >= self.size.height {
+            return;
+        }
+        let location = self.location_at(position);
+        if let Some(index) = self
+            .other_carets
+            .iter()
+            .position(|caret| caret.location == location)
+        {
+            self.other_carets.remove(index);
+        } else if location == self.text_location {
+            let Some(caret) = self.other_carets.pop() else {
+                return;
+            };
+            self.text_location = caret.location;
+            self.selection_anchor = caret.anchor;
+        } else {
+            self.push_main_caret(location);
+            self.merge_carets();
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Adds a caret below the last caret, or above the first one, at the column of the main caret
+    fn add_caret_on_next_line(&mut self, below: bool) {
+        let line_indexes = std::iter::once(self.text_location.line_index).chain(
+            self.other_carets
+                .iter()
+                .map(|caret| caret.location.line_index),
+        );
+        let line_index = if below {
+            line_indexes
+                .max()
+                .map(|line_index| line_index.saturating_add(1))
+        } else {
+            line_indexes
+                .min()
+                .and_then(|line_index| line_index.checked_sub(1))
+        };
+        let Some(line_index) = line_index.filter(|line_index| *line_index < self.buffer.height())
+        else {
+            return;
+        };
+        let col = self.text_location_to_position().col;
+        let grapheme_index = self
+            .buffer
+            .lines
+            .get(line_index)
+            .map_or(0, |line| line.grapheme_index_at(col));
+        self.push_main_caret(Location {
+            grapheme_index,
+            line_index,
+        });
+    }
+
+    /// Selects the word at the caret if nothing is selected.
+    /// Otherwise, adds a caret selecting the next occurrence of the selected text which has no caret yet.
+    fn add_next_occurrence(&mut self) {
+        let Some((start, end)) = self.selection_range() else {
+            self.select_word_at_caret();
+            return;
+        };
+        if start.line_index != end.line_index || self.is_selection_linewise {
+            return;
+        }
+        let text = self.buffer.text_in_range(&start, &end);
+        let length = end.grapheme_index.saturating_sub(start.grapheme_index);
+        let mut from = end;
+        // Each occurrence selected by a caret is skipped at most once before coming back to the main selection
+        for _ in 0..=self.other_carets.len() {
+            let Some(found) = self.buffer.find_next(&text, &from) else {
+                return;
+            };
+            if found == start {
+                return;
+            }
+            let found_end = Location {
+                grapheme_index: found.grapheme_index.saturating_add(length),
+                line_index: found.line_index,
+            };
+            let is_selected = self.other_carets.iter().any(|caret| {
+                caret
+                    .anchor
+                    .as_ref()
+                    .is_some_and(|anchor| min(anchor, &caret.location) == &found)
+            });
+            if !is_selected {
+                self.push_main_caret(found_end);
+                self.selection_anchor = Some(found);
+                return;
+            }
+            from = found_end;
         }
     }
 
+    /// Returns the location of the text drawn at `position` on the screen.
+    /// Positions past the end of a line give the end of that line, positions under the last line give the last line.
+    fn location_at(&self, position: Position) -> Location {
+        let line_index = min(
+            position.row.saturating_add(self.scroll_offset.row),
+            self.buffer.height().saturating_sub(1),
+        );
+        let col = position.col.saturating_add(self.scroll_offset.col);
+        let grapheme_index = self
+            .buffer
+            .lines
+            .get(line_index)
+            .map_or(0, |line| line.grapheme_index_at(col));
+        Location {
+            grapheme_index,
+            line_index,
+        }
+    }
+
+    fn click(&mut self, position: Position) {
+        if position.row >= self.size.height {
+            return;
+        }
+        self.clear_selection();
+        self.text_location = self.location_at(position);
+        self.scroll_text_location_into_view();
+    }
+
+    /// Extends the selection from where the drag started to `position`.
+    /// Dragging below the view selects the next line and scrolls.
+    fn drag(&mut self, position: Position) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.text_location.clone());
+        }
+        self.text_location = self.location_at(Position {
+            row: min(position.row, self.size.height),
+            col: position.col,
+        });
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    fn select_word(&mut self, position: Position) {
+        if position.row >= self.size.height {
+            return;
+        }
+        self.click(position);
+        self.select_word_at_caret();
+    }
+
+    fn select_word_at_caret(&mut self) {
+        let Location {
+            grapheme_index,
+            line_index,
+        } = self.text_location;
+        if let Some(line) = self.buffer.lines.get(line_index) {
+            let word = line.word_range_at(grapheme_index);
+            self.selection_anchor = Some(Location {
+                grapheme_index: word.start,
+                line_index,
+            });
+            self.text_location.grapheme_index = word.end;
+            self.scroll_text_location_into_view();
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Moves the view by [`SCROLL_STEP`], the caret can end up outside of it
+    fn scroll(&mut self, direction: Direction) {
+        let Position { row, col } = self.scroll_offset;
+        self.scroll_offset = match direction {
+            Direction::Up => Position {
+                col,
+                row: row.saturating_sub(SCROLL_STEP),
+            },
+            Direction::Down => Position {
+                col,
+                row: min(
+                    row.saturating_add(SCROLL_STEP),
+                    self.buffer.height().saturating_sub(1),
+                ),
+            },
+            Direction::Left => Position {
+                col: col.saturating_sub(SCROLL_STEP),
+                row,
+            },
+            Direction::Right => Position {
+                col: col.saturating_add(SCROLL_STEP),
+                row,
+            },
+            _ => self.scroll_offset,
+        };
+        self.needs_redraw = self.needs_redraw || self.scroll_offset != Position { col, row };
+    }
+
     fn scroll_vertically(&mut self, to: usize) {
         let Size { height, .. } = self.size;
         let offset_changed = if to < self.scroll_offset.row {
@@ -112,7 +1427,7 @@ impl View {
         self.needs_redraw = self.needs_redraw || offset_changed;
     }
     fn scroll_horizontally(&mut self, to: usize) {
-        let Size { width, .. } = self.size;
+        let width = self.text_width();
         let offset_changed = if to < self.scroll_offset.col {
             self.scroll_offset.col = to;
             true
@@ -132,11 +1447,36 @@ impl View {
 
     #[must_use]
     pub fn caret_position(&self) -> Position {
-        self.text_location_to_position()
-            .saturating_sub(self.scroll_offset)
+        let Position { col, row } = self
+            .text_location_to_position()
+            .saturating_sub(self.scroll_offset);
+        Position {
+            col: col.saturating_add(self.gutter_width()),
+            row,
+        }
     }
 
+    /// The caret can be scrolled out of the view with the mouse wheel
+    #[must_use]
+    pub fn is_caret_visible(&self) -> bool {
+        let Position { row, col } = self.text_location_to_position();
+        let Position {
+            row: top,
+            col: left,
+        } = self.scroll_offset;
+        (top..top.saturating_add(self.size.height)).contains(&row)
+            && (left..left.saturating_add(self.text_width())).contains(&col)
+    }
+
+    /// With a block selection, the caret is at its corner, which can be past the end of the line.
+    /// While the view shows bytes, it is on a hex digit.
     fn text_location_to_position(&self) -> Position {
+        if self.buffer.is_binary() {
+            return self.hex_caret.position();
+        }
+        if let Some(block) = self.block {
+            return block.caret;
+        }
         let row = self.text_location.line_index;
         let col = self.buffer.lines.get(row).map_or(0, |line| {
             line.width_until(self.text_location.grapheme_index)
@@ -144,7 +1484,7 @@ impl View {
         Position { col, row }
     }
 
-    fn move_text_location(&mut self, direction: &Direction) {
+    fn move_text_location(&mut self, direction: Direction) {
         let Size { height, .. } = self.size;
         match direction {
             Direction::Up => self.move_up(1),
@@ -155,6 +1495,22 @@ impl View {
             Direction::PageDown => self.move_down(height.saturating_sub(1)),
             Direction::Home => self.move_to_start_of_line(),
             Direction::End => self.move_to_end_of_line(),
+            Direction::WordLeft => {
+                self.text_location = self.buffer.word_start_before(&self.text_location);
+            }
+            Direction::WordRight => {
+                self.text_location = self.buffer.word_end_after(&self.text_location);
+            }
+            Direction::NextWordStart => {
+                self.text_location = self.buffer.word_start_after(&self.text_location);
+            }
+            Direction::FileStart => self.text_location = Location::default(),
+            Direction::FileEnd => {
+                self.text_location = Location {
+                    grapheme_index: 0,
+                    line_index: self.buffer.height().saturating_sub(1),
+                };
+            }
         }
         self.scroll_text_location_into_view();
     }
@@ -188,16 +1544,69 @@ impl View {
         }
     }
 
-    // clippy::arithmetic_side_effects: This function performs arithmetic calculations
-    // after explicitly checking that the target value will be within bounds.
-    #[allow(clippy::arithmetic_side_effects)]
-    fn move_left(&mut self) {
-        if self.text_location.grapheme_index > 0 {
-            self.text_location.grapheme_index -= 1;
-        } else if self.text_location.line_index > 0 {
-            self.move_up(1);
-            self.move_to_end_of_line();
+    // clippy::arithmetic_side_effects: This function performs arithmetic calculations
+    // after explicitly checking that the target value will be within bounds.
+    #[allow(clippy::arithmetic_side_effects)]
+    fn move_left(&mut self) {
+        if self.text_location.grapheme_index > 0 {
+            self.text_location.grapheme_index -= 1;
+        } else if self.text_location.line_index > 0 {
+            self.move_up(1);
+            self.move_to_end_of_line();
+        }
+    }
+
+    /// Moves the caret to `target`, clamped to the text, and centers its line in the view.
+    /// Without a column, the caret goes to the start of the line.
+    fn go_to(&mut self, target: GoToTarget) {
+        if self.buffer.is_loading() {
+            self.pending_go_to = Some(target);
+            return;
+        }
+        let last_line_index = self.buffer.height().saturating_sub(1);
+        self.text_location.line_index = match target.line {
+            LineTarget::Number(number) => number.saturating_sub(1),
+            LineTarget::Relative(delta) => {
+                self.text_location.line_index.saturating_add_signed(delta)
+            }
+            // We allow this because a percentage of the file does not need to be exact
+            #[allow(clippy::integer_division)]
+            LineTarget::Percent(percent) => last_line_index.saturating_mul(percent.min(100)) / 100,
+        };
+        self.text_location.grapheme_index =
+            target.column.map_or(0, |column| column.saturating_sub(1));
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.center_text_location();
+    }
+
+    /// Scrolls so that the line of the caret is in the middle of the view
+    fn center_text_location(&mut self) {
+        //We allow this because it doesn't matter if the line is not exactly in the middle
+        #[allow(clippy::integer_division)]
+        let half_height = self.size.height / 2;
+        let top = self
+            .text_location_to_position()
+            .row
+            .saturating_sub(half_height);
+        if top != self.scroll_offset.row {
+            self.scroll_offset.row = top;
+            self.needs_redraw = true;
         }
+        self.scroll_text_location_into_view();
+    }
+
+    /// Goes to the bracket matching the one under or just before the caret.
+    /// When the caret is not next to a bracket, goes to the opening bracket of the pair around it.
+    fn jump_to_matching_bracket(&mut self) {
+        let Some((first, second)) = self.buffer.bracket_pair(&self.text_location) else {
+            return;
+        };
+        let is_next_to_caret = first.line_index == self.text_location.line_index
+            && (first.grapheme_index == self.text_location.grapheme_index
+                || first.grapheme_index.saturating_add(1) == self.text_location.grapheme_index);
+        self.text_location = if is_next_to_caret { second } else { first };
+        self.scroll_text_location_into_view();
     }
 
     fn move_to_start_of_line(&mut self) {
@@ -234,14 +1643,403 @@ impl View {
         self.needs_redraw = true;
     }
 
+    /// Shows the text of the file `file_name`, or an empty text named after it if it does not exist yet.
+    /// When the file can not be read, the view stays empty and its message tells why.
     pub fn load(&mut self, file_name: &str) {
-        if let Ok(buffer) = Buffer::load(file_name) {
-            self.buffer = buffer;
+        match Buffer::load(file_name) {
+            Ok(buffer) => self.buffer = buffer,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                self.buffer = Buffer::named(file_name);
+            }
+            Err(error) => self.message = Some(format!("Could not open {file_name}: {error}")),
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Shows the text of `source` as it is read in the background, the view has no file then
+    pub fn stream<R: Read + Send + 'static>(&mut self, source: R) {
+        self.buffer = Buffer::stream(source);
+        self.needs_redraw = true;
+    }
+
+    #[must_use]
+    pub fn has_file(&self) -> bool {
+        self.file_buffer().file_name.is_some()
+    }
+
+    /// The buffer of the file, which is not the one shown while the view is filtered
+    fn file_buffer(&self) -> &Buffer {
+        self.filter
+            .as_ref()
+            .map_or(&self.buffer, |filter| &filter.buffer)
+    }
+
+    fn file_buffer_mut(&mut self) -> &mut Buffer {
+        match &mut self.filter {
+            Some(filter) => &mut filter.buffer,
+            None => &mut self.buffer,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Buffer::save`] fails
+    pub fn save(&mut self) -> Result<(), Error> {
+        self.file_buffer_mut().save()
+    }
+
+    /// Saves the text to `file_name`, the view then edits that file.
+    /// The text is drawn again since its file type, and so its highlighting, may have changed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Buffer::save_as`] fails
+    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
+        self.file_buffer_mut().save_as(file_name)?;
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    /// Gives the text to the swap file if it changed, see [`Buffer::update_swap`]
+    pub fn update_swap(&mut self) {
+        self.file_buffer_mut().update_swap();
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Buffer::write_swap`] fails
+    pub fn write_swap(&mut self) -> Result<(), Error> {
+        self.file_buffer_mut().write_swap()
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Buffer::remove_swap`] fails
+    pub fn remove_swap(&mut self) -> Result<(), Error> {
+        self.file_buffer_mut().remove_swap()
+    }
+
+    pub fn check_swap(&mut self) -> Option<PathBuf> {
+        self.file_buffer_mut().check_swap()
+    }
+
+    pub fn unnamed_swap_path(&self) -> Option<PathBuf> {
+        self.file_buffer().unnamed_swap_path()
+    }
+
+    /// Puts the text of the swap file `path` in the view
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Buffer::recover`] fails
+    pub fn recover(&mut self, path: &Path) -> Result<(), Error> {
+        self.clear_filter();
+        self.buffer.recover(path)?;
+        self.text_replaced();
+        Ok(())
+    }
+
+    /// Puts the text of the file, as it is now on the disk, in the view
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Buffer::reload`] fails
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.clear_filter();
+        self.buffer.reload()?;
+        self.text_replaced();
+        Ok(())
+    }
+
+    /// The selections and the other carets are dropped, the caret stays where it was if it still can
+    fn text_replaced(&mut self) {
+        self.clear_selection();
+        self.other_carets.clear();
+        self.block = None;
+        self.previous_command = None;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        if let Some(bytes) = self.buffer.bytes() {
+            self.hex_caret = self.hex_caret.clamped(bytes.len());
+        }
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    #[must_use]
+    pub fn is_modified(&self) -> bool {
+        self.file_buffer().is_modified()
+    }
+
+    #[must_use]
+    pub fn is_changed_on_disk(&self) -> bool {
+        self.file_buffer().is_changed_on_disk()
+    }
+
+    pub fn ignore_disk_changes(&mut self) {
+        self.file_buffer_mut().ignore_disk_changes();
+    }
+
+    /// Returns a read-only view, of the same size, showing how the file `path` differs from the text
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Buffer::diff_with`] fails
+    pub fn diff_with(&self, path: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            buffer: self.file_buffer().diff_with(path)?,
+            size: self.size,
+            ..Self::default()
+        })
+    }
+
+    /// Runs a deletion command when there is no selection to delete
+    fn delete_at_caret(&mut self, command: &EditorCommand) {
+        match command {
+            EditorCommand::Delete => self.delete(),
+            EditorCommand::Backspace => self.backspace(),
+            EditorCommand::DeleteWordBackward => self.delete_word(false),
+            EditorCommand::DeleteWordForward => self.delete_word(true),
+            _ => {}
+        }
+    }
+
+    /// Edits of a read-only buffer are refused with a message saying why
+    fn refuses(&mut self, command: &EditorCommand) -> bool {
+        let adds_line = matches!(
+            command,
+            EditorCommand::Enter | EditorCommand::OpenLineBelow | EditorCommand::OpenLineAbove
+        );
+        if adds_line && self.buffer.keeps_line_count() {
+            self.message = Some("Lines can not be added while filtering".to_string());
+            return true;
+        }
+        if !command.edits_text() || !self.buffer.is_read_only() {
+            return false;
+        }
+        self.message = Some(if self.buffer.is_loading() {
+            "The file is still loading".to_string()
+        } else {
+            "The buffer is read-only".to_string()
+        });
+        true
+    }
+
+    pub fn take_message(&mut self) -> Option<String> {
+        self.message.take()
+    }
+
+    pub fn set_needs_redraw(&mut self) {
+        self.needs_redraw = true;
+    }
+
+    /// Gives the copied and cut texts to `other`, to paste them in another file
+    pub fn move_kill_ring_to(&mut self, other: &mut Self) {
+        other.kill_ring = std::mem::take(&mut self.kill_ring);
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.buffer.set_read_only(read_only);
+        self.file_buffer_mut().set_read_only(read_only);
+    }
+
+    pub fn set_keeps_backup(&mut self, keeps_backup: bool) {
+        self.file_buffer_mut().set_keeps_backup(keeps_backup);
+    }
+
+    #[must_use]
+    pub fn is_filtered(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    /// Shows only the lines matching `pattern`, see [`Pattern`], with their real line numbers before them.
+    /// They can be edited, but not split or joined. The caret goes to the first shown line from its line.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the pattern is not valid, no line matches it or the file is loading, followed or binary
+    pub fn filter(&mut self, pattern: &str) -> Result<(), String> {
+        let pattern: Pattern = pattern.parse()?;
+        self.clear_filter();
+        if self.buffer.is_binary() {
+            return Err("Binary files can not be filtered".to_string());
+        }
+        if self.buffer.is_loading() {
+            return Err("The file is still loading".to_string());
+        }
+        if self.buffer.is_following() {
+            return Err("Stop following the file to filter it".to_string());
+        }
+        let line_indexes = pattern.matching_lines(&self.buffer);
+        if line_indexes.is_empty() {
+            return Err(format!("No line matches {pattern}"));
+        }
+        let (filter, shown) = Filter::new(std::mem::take(&mut self.buffer), line_indexes);
+        self.text_location.line_index = filter.shown_index(self.text_location.line_index);
+        self.buffer = shown;
+        self.filter = Some(filter);
+        self.scroll_offset = Position::default();
+        self.text_replaced();
+        self.center_text_location();
+        Ok(())
+    }
+
+    /// Shows all the lines again, with the caret on the line it was on
+    pub fn clear_filter(&mut self) {
+        let Some(mut filter) = self.filter.take() else {
+            return;
+        };
+        filter.sync(&self.buffer);
+        self.text_location.line_index = filter
+            .line_index(self.text_location.line_index)
+            .unwrap_or_default();
+        self.buffer = filter.buffer;
+        self.scroll_offset = Position::default();
+        self.text_replaced();
+        self.center_text_location();
+    }
+
+    /// The width of the real line numbers drawn before the lines of a filtered view
+    fn gutter_width(&self) -> usize {
+        self.filter.as_ref().map_or(0, Filter::gutter_width)
+    }
+
+    /// The width left for the text once the line numbers are drawn
+    fn text_width(&self) -> usize {
+        self.size.width.saturating_sub(self.gutter_width())
+    }
+
+    /// Starts following the file from its last line, or stops following it
+    fn toggle_follow(&mut self) {
+        if self.buffer.is_following() {
+            self.buffer.stop_following();
+            self.message = Some("Stopped following the file".to_string());
+            return;
+        }
+        self.clear_filter();
+        if let Err(error) = self.buffer.follow() {
+            self.message = Some(error.to_string());
+        } else {
+            self.text_replaced();
+            self.go_to(GoToTarget {
+                line: LineTarget::Percent(100),
+                column: None,
+            });
+        }
+    }
+
+    /// Shows the bytes of the text in hex, or the text of the bytes if they are text, the caret staying on its line
+    fn toggle_hex(&mut self) {
+        if let Some(bytes) = self.buffer.bytes() {
+            let line_index = bytes
+                .iter()
+                .take(self.hex_caret.offset)
+                .filter(|byte| **byte == b'\n')
+                .count();
+            if let Err(error) = self.buffer.make_text() {
+                self.message = Some(error.to_string());
+                return;
+            }
+            self.text_location = Location {
+                line_index,
+                grapheme_index: 0,
+            };
+        } else {
+            self.clear_filter();
+            let offset = self
+                .buffer
+                .lines
+                .iter()
+                .take(self.text_location.line_index)
+                .map(|line| line.to_string().len().saturating_add(1))
+                .sum();
+            if let Err(error) = self.buffer.make_binary() {
+                self.message = Some(error.to_string());
+                return;
+            }
+            self.hex_caret = HexCaret::at(offset);
+        }
+        self.scroll_offset = Position::default();
+        self.text_replaced();
+        self.center_text_location();
+    }
+
+    /// The caret is on the last line and the view shows it, the view scrolls down with the followed file then
+    fn is_at_last_line(&self) -> bool {
+        let last_line_index = self.buffer.height().saturating_sub(1);
+        let bottom = self.scroll_offset.row.saturating_add(self.size.height);
+        self.text_location.line_index >= last_line_index && bottom > last_line_index
+    }
+
+    /// Takes the lines written at the end of the followed file since the last call.
+    /// The view scrolls down with them while the caret is on the last line: moving it or scrolling up pauses that.
+    /// Returns what to tell the user if the file was truncated or replaced.
+    pub fn poll_following(&mut self) -> Option<&'static str> {
+        let was_at_last_line = self.is_at_last_line();
+        let growth = self.buffer.poll_follower()?;
+        if growth != Growth::Appended {
+            self.text_replaced();
+        }
+        if was_at_last_line {
+            self.text_location = Location {
+                line_index: self.buffer.height().saturating_sub(1),
+                grapheme_index: 0,
+            };
+            self.scroll_text_location_into_view();
+        }
+        self.needs_redraw = true;
+        match growth {
+            Growth::Appended => None,
+            Growth::Truncated => Some("The file was truncated, following it from the start"),
+            Growth::Replaced => Some("The file was replaced, following the new one"),
+        }
+    }
+
+    /// Takes the lines read in the background since the last call, if the file is still loading
+    pub fn poll_loading(&mut self) {
+        let old_height = self.buffer.height();
+        let bottom = self.scroll_offset.row.saturating_add(self.size.height);
+        if self.buffer.poll_loader() && old_height < bottom {
             self.needs_redraw = true;
         }
+        if !self.buffer.is_loading() {
+            if let Some(target) = self.pending_go_to.take() {
+                self.go_to(target);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn get_status(&self) -> DocumentStatus {
+        let line_index = self.text_location.line_index;
+        DocumentStatus {
+            total_lines: self.file_buffer().height(),
+            current_line_index: self
+                .filter
+                .as_ref()
+                .map_or(Some(line_index), |filter| filter.line_index(line_index))
+                .unwrap_or_default(),
+            file_name: self.file_buffer().file_name.clone(),
+            load_progress: self.buffer.load_progress(),
+            is_read_only: self.buffer.is_read_only(),
+            following: self.buffer.is_following().then(|| self.is_at_last_line()),
+            shown_lines: self.filter.as_ref().map(Filter::shown_count),
+            hex_offset: self
+                .buffer
+                .bytes()
+                .map(|bytes| (self.hex_caret.offset, bytes.len())),
+            mode: None,
+            buffer: None,
+        }
     }
 
     fn insert_char(&mut self, character: char) {
+        if self.buffer.is_read_only() {
+            return;
+        }
+        let outdent = self.buffer.outdent_before(character, &self.text_location);
+        self.text_location.grapheme_index =
+            self.text_location.grapheme_index.saturating_sub(outdent);
         let old_len = self
             .buffer
             .lines
@@ -257,10 +2055,14 @@ impl View {
         if grapheme_delta > 0 {
             self.move_right();
         }
+        self.scroll_text_location_into_view();
         self.needs_redraw = true;
     }
 
     fn backspace(&mut self) {
+        if self.buffer.is_read_only() {
+            return;
+        }
         if self.text_location.line_index == 0 && self.text_location.grapheme_index == 0 {
             return;
         }
@@ -269,7 +2071,7 @@ impl View {
     }
 
     fn delete(&mut self) {
-        if self.end_of_file() {
+        if self.buffer.is_read_only() || self.end_of_file() {
             return;
         }
         if self.start_of_view() {
@@ -280,6 +2082,49 @@ impl View {
         self.needs_redraw = true;
     }
 
+    /// Deletes from the caret to the end of the next word if `forward`, to the start of the previous word otherwise.
+    /// Like the word moves, this joins lines at their ends.
+    fn delete_word(&mut self, forward: bool) {
+        if self.buffer.is_read_only() {
+            return;
+        }
+        let (start, end) = if forward {
+            (
+                self.text_location.clone(),
+                self.buffer.word_end_after(&self.text_location),
+            )
+        } else {
+            (
+                self.buffer.word_start_before(&self.text_location),
+                self.text_location.clone(),
+            )
+        };
+        if start == end {
+            return;
+        }
+        self.buffer.delete_range(&start, &end);
+        self.text_location = start;
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
+    /// Inserts an indented line below or above the current one and puts the caret on it
+    fn open_line(&mut self, above: bool) {
+        if self.buffer.is_read_only() {
+            return;
+        }
+        if above {
+            self.text_location = self.buffer.open_line_above(self.text_location.line_index);
+        } else if self.buffer.is_empty() {
+            self.text_location = self.buffer.open_line_above(0);
+        } else {
+            self.move_to_end_of_line();
+            self.text_location = self.buffer.insert_line(&self.text_location);
+        }
+        self.scroll_text_location_into_view();
+        self.needs_redraw = true;
+    }
+
     fn end_of_file(&self) -> bool {
         self.text_location.line_index == self.buffer.height().saturating_sub(1)
             && self.text_location.grapheme_index
@@ -287,8 +2132,7 @@ impl View {
                     .buffer
                     .lines
                     .get(self.text_location.line_index)
-                    .unwrap()
-                    .grapheme_count()
+                    .map_or(0, Line::grapheme_count)
     }
 
     fn start_of_view(&self) -> bool {
@@ -296,8 +2140,11 @@ impl View {
     }
 
     fn insert_line(&mut self) {
-        self.buffer.insert_line(&self.text_location);
-        self.move_text_location(&Direction::Right);
+        if self.buffer.is_read_only() {
+            return;
+        }
+        self.text_location = self.buffer.insert_line(&self.text_location);
+        self.scroll_text_location_into_view();
         self.needs_redraw = true;
     }
 }
@@ -307,9 +2154,20 @@ impl Default for View {
         Self {
             buffer: Buffer::default(),
             needs_redraw: true,
-            size: Terminal::get_size().unwrap(),
+            size: Size::default(),
             text_location: Location::default(),
             scroll_offset: Position::default(),
+            selection_anchor: None,
+            is_selection_linewise: false,
+            other_carets: Vec::new(),
+            block: None,
+            kill_ring: Vec::new(),
+            previous_command: None,
+            pending_go_to: None,
+            brackets_caret: None,
+            message: None,
+            filter: None,
+            hex_caret: HexCaret::default(),
         }
     }
 }
@@ -319,8 +2177,14 @@ mod test {
 
     use super::*;
 
+    const TEST_SIZE: Size = Size {
+        width: 80,
+        height: 24,
+    };
+
     fn set_up(file_name: &str) -> View {
         let mut view = View::default();
+        view.resize(TEST_SIZE);
         view.load(file_name);
         view
     }
@@ -338,17 +2202,16 @@ mod test {
 
     #[test]
     fn test_offset_change_scroll() {
-        let terminal_size: (u16, u16) = crossterm::terminal::size().unwrap();
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
-        view.move_down(terminal_size.1.into());
+        view.move_down(TEST_SIZE.height);
         view.scroll_text_location_into_view();
         assert_eq!(view.scroll_offset.row, 1);
     }
 
     #[test]
     fn test_scroll_to_end() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         let mut height = view.buffer.height();
         while height > 0 {
             view.move_down(1);
@@ -363,16 +2226,17 @@ mod test {
     #[test]
     fn test_must_scroll() {
         let mut view: View = View::default();
+        view.resize(TEST_SIZE);
         assert!(view.needs_redraw);
         view.needs_redraw = false;
-        view.load(".\\src\\editor.rs");
+        view.load("./text-test/test-4.txt");
         view.handle_command(EditorCommand::Move(Direction::PageDown));
         assert!(view.needs_redraw);
     }
 
     #[test]
     fn test_scroll_down_end() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert_eq!(view.text_location.line_index, 0);
         view.move_down(view.buffer.height().saturating_sub(1));
         let line_index = view.text_location.line_index;
@@ -382,7 +2246,7 @@ mod test {
 
     #[test]
     fn test_scroll_up_beginning() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert_eq!(view.text_location.line_index, 0);
         view.move_up(1);
         assert_eq!(view.text_location.line_index, 0);
@@ -390,7 +2254,7 @@ mod test {
 
     #[test]
     fn test_screen_scroll_up() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(1);
         let line_width = view
@@ -424,7 +2288,7 @@ mod test {
     }
     #[test]
     fn test_screen_scroll_down() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(1);
         let line_width = view
@@ -459,7 +2323,7 @@ mod test {
 
     #[test]
     fn test_page_down() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(1);
         view.handle_command(EditorCommand::Move(Direction::PageDown));
@@ -469,7 +2333,7 @@ mod test {
 
     #[test]
     fn test_page_up() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.handle_command(EditorCommand::Move(Direction::PageDown));
         view.move_down(1);
@@ -480,7 +2344,7 @@ mod test {
 
     #[test]
     fn test_move_right() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(2);
         view.move_right();
@@ -504,7 +2368,7 @@ mod test {
 
     #[test]
     fn test_move_right_at_bottom() {
-        let mut view = set_up(".\\src\\editor.rs");
+        let mut view = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         while view.text_location.line_index < view.buffer.height().saturating_sub(1) {
             view.handle_command(EditorCommand::Move(Direction::PageDown));
@@ -518,7 +2382,7 @@ mod test {
 
     #[test]
     fn test_move_left() {
-        let mut view = set_up(".\\src\\editor.rs");
+        let mut view = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(3);
         view.move_left();
@@ -546,7 +2410,7 @@ mod test {
 
     #[test]
     fn test_move_left_start() {
-        let mut view = set_up(".\\src\\editor.rs");
+        let mut view = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.move_right();
         assert_eq!(view.text_location.grapheme_index, 1);
@@ -559,7 +2423,7 @@ mod test {
 
     #[test]
     fn test_scroll_offset_move_left() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(1);
         view.move_left();
@@ -571,13 +2435,13 @@ mod test {
                 .first()
                 .unwrap()
                 .grapheme_count()
-                .saturating_sub(Terminal::get_size().unwrap().width)
+                .saturating_sub(TEST_SIZE.width)
         );
     }
 
     #[test]
     fn test_scroll_offset_move_right() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(1);
         view.move_to_end_of_line();
@@ -589,7 +2453,7 @@ mod test {
 
     #[test]
     fn test_scroll_offset_move_right_end() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.handle_command(EditorCommand::Move(Direction::PageDown));
         assert_eq!(view.scroll_offset.row, 0);
@@ -601,9 +2465,8 @@ mod test {
 
     #[test]
     fn test_scroll_offset_move_left_start() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
-        view.resize(Terminal::get_size().unwrap());
         view.handle_command(EditorCommand::Move(Direction::PageDown));
         view.move_down(1);
         view.scroll_text_location_into_view();
@@ -620,13 +2483,13 @@ mod test {
                 .first()
                 .unwrap()
                 .grapheme_count()
-                .saturating_sub(Terminal::get_size().unwrap().width)
+                .saturating_sub(TEST_SIZE.width)
         );
     }
 
     #[test]
     fn test_move_up_position() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(4);
         view.move_to_end_of_line();
@@ -653,7 +2516,7 @@ mod test {
 
     #[test]
     fn test_move_down_position() {
-        let mut view: View = set_up(".\\src\\editor.rs");
+        let mut view: View = set_up("./text-test/test-4.txt");
         assert!(!view.buffer.is_empty());
         view.move_down(5);
         view.move_to_end_of_line();
@@ -680,7 +2543,7 @@ mod test {
 
     #[test]
     fn test_delete_character_at_end_line() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         let current_line_width = view.buffer.lines.first().unwrap().grapheme_count();
         let next_line_width = view.buffer.lines.get(1).unwrap().grapheme_count();
@@ -696,7 +2559,7 @@ mod test {
 
     #[test]
     fn test_backspace_at_start_line() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         let current_line_width = view.buffer.lines.first().unwrap().grapheme_count();
         let next_line_width = view.buffer.lines.get(1).unwrap().grapheme_count();
@@ -712,7 +2575,7 @@ mod test {
 
     #[test]
     fn test_backspace_scroll() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         for _ in 0..2 {
             view.handle_command(EditorCommand::Move(Direction::PageDown));
@@ -732,7 +2595,7 @@ mod test {
 
     #[test]
     fn test_line_width_tab() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         let line_width = view.buffer.lines.first().unwrap().grapheme_count();
         view.handle_command(EditorCommand::Insert('\t'));
@@ -744,7 +2607,7 @@ mod test {
 
     #[test]
     fn test_enter() {
-        let mut view: View = set_up(".\\text-test\\test-3.txt");
+        let mut view: View = set_up("./text-test/test-3.txt");
         assert!(!view.buffer.is_empty());
         for _ in 0..4 {
             view.move_down(1);
@@ -776,4 +2639,264 @@ mod test {
         assert_eq!(0, view.text_location.grapheme_index);
         assert_eq!(5, view.text_location.line_index);
     }
+
+    #[test]
+    fn test_click_full_width_graphemes() {
+        let mut view: View = set_up("./text-test/test-2.txt");
+        view.handle_command(EditorCommand::Click(Position { col: 5, row: 7 }));
+        assert_eq!(view.text_location.line_index, 7);
+        assert_eq!(view.text_location.grapheme_index, 2);
+        view.handle_command(EditorCommand::Click(Position { col: 4, row: 7 }));
+        assert_eq!(view.text_location.grapheme_index, 2);
+        view.handle_command(EditorCommand::Click(Position { col: 70, row: 9 }));
+        assert_eq!(
+            view.text_location.grapheme_index,
+            view.buffer.lines.get(9).unwrap().grapheme_count()
+        );
+    }
+
+    #[test]
+    fn test_click_scrolled() {
+        let mut view: View = set_up("./text-test/test-3.txt");
+        view.scroll(Direction::Down);
+        view.handle_command(EditorCommand::Click(Position { col: 2, row: 0 }));
+        assert_eq!(view.text_location.line_index, SCROLL_STEP);
+        assert_eq!(view.text_location.grapheme_index, 2);
+    }
+
+    #[test]
+    fn test_drag_and_delete_selection() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::Click(Position { col: 5, row: 0 }));
+        view.handle_command(EditorCommand::Drag(Position { col: 5, row: 1 }));
+        assert!(view.selection_range().is_some());
+        view.handle_command(EditorCommand::Backspace);
+        assert_eq!(
+            view.buffer.lines.first().unwrap().to_string(),
+            "rust is 𝒻𝒶𝓃𝒸𝓎!"
+        );
+        assert_eq!(view.buffer.height(), 3);
+        assert!(view.selection_range().is_none());
+    }
+
+    #[test]
+    fn test_drag_backwards_and_type() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::Click(Position { col: 13, row: 0 }));
+        view.handle_command(EditorCommand::Drag(Position { col: 8, row: 0 }));
+        view.handle_command(EditorCommand::Insert('?'));
+        assert_eq!(view.buffer.lines.first().unwrap().to_string(), "rust is ?!");
+    }
+
+    #[test]
+    fn test_double_click_selects_word() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::DoubleClick(Position { col: 6, row: 0 }));
+        let (start, end) = view.selection_range().unwrap();
+        assert_eq!((start.grapheme_index, end.grapheme_index), (5, 7));
+        view.handle_command(EditorCommand::Move(Direction::Right));
+        assert!(view.selection_range().is_none());
+    }
+
+    #[test]
+    fn test_wheel_does_not_move_caret() {
+        let mut view: View = set_up("./text-test/test-3.txt");
+        view.scroll(Direction::Down);
+        assert_eq!(view.scroll_offset.row, SCROLL_STEP);
+        assert_eq!(view.text_location, Location::default());
+        assert!(!view.is_caret_visible());
+        view.handle_command(EditorCommand::Move(Direction::Right));
+        assert!(view.is_caret_visible());
+    }
+
+    #[test]
+    fn test_jump_to_matching_bracket() {
+        let mut view: View = set_up("./text-test/test-4.txt");
+        view.move_down(13);
+        view.move_to_end_of_line();
+        view.handle_command(EditorCommand::JumpToMatchingBracket);
+        assert_eq!(
+            view.text_location,
+            Location {
+                grapheme_index: 0,
+                line_index: 16
+            }
+        );
+        view.handle_command(EditorCommand::JumpToMatchingBracket);
+        assert_eq!(
+            view.text_location,
+            Location {
+                grapheme_index: 18,
+                line_index: 13
+            }
+        );
+    }
+
+    #[test]
+    fn test_delete_word() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::Move(Direction::WordRight));
+        view.handle_command(EditorCommand::Move(Direction::WordRight));
+        assert_eq!(view.text_location.grapheme_index, 7);
+        view.handle_command(EditorCommand::DeleteWordBackward);
+        assert_eq!(
+            view.buffer.lines.first().unwrap().to_string(),
+            "rust  great!"
+        );
+        view.handle_command(EditorCommand::DeleteWordForward);
+        assert_eq!(view.buffer.lines.first().unwrap().to_string(), "rust !");
+        view.handle_command(EditorCommand::Move(Direction::End));
+        view.handle_command(EditorCommand::DeleteWordForward);
+        assert!(view
+            .buffer
+            .lines
+            .first()
+            .unwrap()
+            .to_string()
+            .starts_with("rust !rust is"));
+    }
+
+    fn lines(view: &View) -> Vec<String> {
+        view.buffer.lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_carets_on_next_lines() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::Move(Direction::WordRight));
+        view.handle_command(EditorCommand::AddCaretBelow);
+        view.handle_command(EditorCommand::AddCaretBelow);
+        assert_eq!(view.other_carets.len(), 2);
+        view.handle_command(EditorCommand::Insert('!'));
+        view.handle_command(EditorCommand::Enter);
+        view.handle_command(EditorCommand::Backspace);
+        view.handle_command(EditorCommand::Backspace);
+        view.handle_command(EditorCommand::Move(Direction::Right));
+        view.handle_command(EditorCommand::Delete);
+        assert_eq!(
+            lines(&view).get(..3).unwrap(),
+            ["rusti great!", "rusti 𝒻𝒶𝓃𝒸𝓎!", "rusti ѕ¢αяу!"]
+        );
+        assert_eq!(
+            view.text_location,
+            Location {
+                grapheme_index: 5,
+                line_index: 2
+            }
+        );
+        view.handle_command(EditorCommand::Move(Direction::FileStart));
+        assert!(view.other_carets.is_empty());
+        view.handle_command(EditorCommand::AddCaretAbove);
+        assert!(view.other_carets.is_empty());
+        view.handle_command(EditorCommand::ClearSelection);
+        view.handle_command(EditorCommand::Insert('a'));
+        assert_eq!(lines(&view).first().unwrap(), "arusti great!");
+    }
+
+    #[test]
+    fn test_add_next_occurrence() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::Move(Direction::Down));
+        view.handle_command(EditorCommand::AddNextOccurrence);
+        assert_eq!(
+            view.selection_range()
+                .map(|(start, end)| (start.grapheme_index, end.grapheme_index)),
+            Some((0, 4))
+        );
+        for _ in 0..4 {
+            view.handle_command(EditorCommand::AddNextOccurrence);
+        }
+        assert_eq!(view.other_carets.len(), 2);
+        view.handle_command(EditorCommand::Insert('R'));
+        view.handle_command(EditorCommand::Move(Direction::End));
+        view.handle_command(EditorCommand::Insert('?'));
+        assert_eq!(
+            lines(&view).get(..3).unwrap(),
+            ["R is great!?", "R is 𝒻𝒶𝓃𝒸𝓎!?", "R is ѕ¢αяу!?"]
+        );
+        assert_eq!(view.text_location.line_index, 0);
+    }
+
+    #[test]
+    fn test_toggle_and_merge_carets() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::ToggleCaret(Position { col: 3, row: 1 }));
+        view.handle_command(EditorCommand::ToggleCaret(Position { col: 5, row: 1 }));
+        view.handle_command(EditorCommand::ToggleCaret(Position { col: 5, row: 1 }));
+        assert_eq!(view.other_carets.len(), 1);
+        assert_eq!(
+            view.text_location,
+            Location {
+                grapheme_index: 3,
+                line_index: 1
+            }
+        );
+        view.handle_command(EditorCommand::Move(Direction::Up));
+        view.handle_command(EditorCommand::Move(Direction::Home));
+        assert!(view.other_carets.is_empty());
+        view.handle_command(EditorCommand::ToggleCaret(Position { col: 1, row: 0 }));
+        view.handle_command(EditorCommand::ToggleCaret(Position { col: 2, row: 0 }));
+        assert_eq!(view.other_carets.len(), 2);
+        view.handle_command(EditorCommand::Backspace);
+        assert!(view.other_carets.is_empty());
+        assert_eq!(lines(&view).first().unwrap(), "st is great!");
+    }
+
+    #[test]
+    fn test_block_selection() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::Move(Direction::WordRight));
+        view.handle_command(EditorCommand::SelectBlock(Direction::Down));
+        view.handle_command(EditorCommand::SelectBlock(Direction::Down));
+        for _ in 0..3 {
+            view.handle_command(EditorCommand::SelectBlock(Direction::Right));
+        }
+        view.handle_command(EditorCommand::Copy);
+        view.handle_command(EditorCommand::Insert('|'));
+        assert_eq!(
+            lines(&view).get(..3).unwrap(),
+            ["rust| great!", "rust| 𝒻𝒶𝓃𝒸𝓎!", "rust| ѕ¢αяу!"]
+        );
+        view.handle_command(EditorCommand::Backspace);
+        view.handle_command(EditorCommand::Delete);
+        assert_eq!(
+            lines(&view).get(..3).unwrap(),
+            ["rustgreat!", "rust𝒻𝒶𝓃𝒸𝓎!", "rustѕ¢αяу!"]
+        );
+        view.handle_command(EditorCommand::Paste);
+        assert!(view.block.is_none());
+        assert_eq!(
+            lines(&view).get(..3).unwrap(),
+            ["rust isgreat!", "rust is𝒻𝒶𝓃𝒸𝓎!", "rust isѕ¢αяу!"]
+        );
+        assert_eq!(
+            view.text_location,
+            Location {
+                grapheme_index: 7,
+                line_index: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_block_past_end_of_lines() {
+        let mut view: View = set_up("./text-test/test.txt");
+        view.handle_command(EditorCommand::Move(Direction::Down));
+        view.handle_command(EditorCommand::Move(Direction::Down));
+        view.handle_command(EditorCommand::Move(Direction::End));
+        for _ in 0..2 {
+            view.handle_command(EditorCommand::SelectBlock(Direction::Right));
+            view.handle_command(EditorCommand::SelectBlock(Direction::Down));
+        }
+        assert_eq!(view.caret_position(), Position { col: 16, row: 3 });
+        view.handle_command(EditorCommand::Cut);
+        view.handle_command(EditorCommand::Insert('#'));
+        assert_eq!(
+            lines(&view).get(2..).unwrap(),
+            ["rust is ѕ¢αяу!#", "ʎzɐɹɔ sı ʇsnɹ #"]
+        );
+        view.handle_command(EditorCommand::Move(Direction::FileEnd));
+        view.handle_command(EditorCommand::Paste);
+        assert_eq!(lines(&view).get(3..).unwrap(), ["ʎzɐɹɔ sı ʇsnɹ #", ""]);
+    }
 }
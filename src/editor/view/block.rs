@@ -0,0 +1,80 @@
+use crate::editor::terminal::Position;
+use crate::editor::view::line::Line;
+use std::cmp::{max, min};
+use std::ops::{Range, RangeInclusive};
+
+/// This represents a rectangle of text, between two corners made of a line index, as `row`, and a column on the screen.
+/// Its sides are columns, which do not depend on the length of the lines it goes over: it can go past their ends.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub anchor: Position,
+    /// The corner moved by the block selection commands, where the caret is shown
+    pub caret: Position,
+}
+
+impl Block {
+    pub fn line_indexes(&self) -> RangeInclusive<usize> {
+        min(self.anchor.row, self.caret.row)..=max(self.anchor.row, self.caret.row)
+    }
+
+    pub fn columns(&self) -> Range<usize> {
+        min(self.anchor.col, self.caret.col)..max(self.anchor.col, self.caret.col)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor.col == self.caret.col
+    }
+
+    /// Returns the graphemes of `line` in the block: the ones drawn in its columns, even partly for full-width graphemes.
+    /// For an empty block, both ends are where text is inserted.
+    pub fn graphemes(&self, line: &Line) -> Range<usize> {
+        let columns = self.columns();
+        let end = line.grapheme_index_from(columns.end);
+        if self.is_empty() {
+            return end..end;
+        }
+        line.grapheme_index_at(columns.start).min(end)..end
+    }
+
+    /// Returns the columns to highlight on `line`: the ones of the block, widened to whole full-width graphemes.
+    /// An empty block is shown as one column, like a caret.
+    pub fn highlighted_columns(&self, line: &Line) -> Range<usize> {
+        let columns = self.columns();
+        let graphemes = self.graphemes(line);
+        if self.is_empty() {
+            let col = max(columns.start, line.width_until(graphemes.start));
+            return col..col.saturating_add(1);
+        }
+        let start = if graphemes.start < line.grapheme_count() {
+            min(columns.start, line.width_until(graphemes.start))
+        } else {
+            columns.start
+        };
+        start..max(columns.end, line.width_until(graphemes.end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn block(from: usize, to: usize) -> Block {
+        Block {
+            anchor: Position { col: from, row: 0 },
+            caret: Position { col: to, row: 2 },
+        }
+    }
+
+    #[test]
+    fn test_graphemes_in_block() {
+        let line = Line::from("aＢcd");
+        assert_eq!(block(3, 1).graphemes(&line), 1..2);
+        assert_eq!(block(2, 4).graphemes(&line), 1..3);
+        assert_eq!(block(2, 4).highlighted_columns(&line), 1..4);
+        assert_eq!(block(2, 2).graphemes(&line), 2..2);
+        assert_eq!(block(2, 2).highlighted_columns(&line), 3..4);
+        assert_eq!(block(6, 9).graphemes(&line), 4..4);
+        assert_eq!(block(6, 9).highlighted_columns(&line), 6..9);
+        assert_eq!(block(1, 9).line_indexes(), 0..=2);
+    }
+}
@@ -1,10 +1,72 @@
+use crate::editor::diff;
+use crate::editor::filetype::{FileType, INDENT};
+use crate::editor::swap::Swap;
+use crate::editor::view::buffer::follower::{Follower, Growth};
+use crate::editor::view::buffer::highlighter::Highlighter;
+use crate::editor::view::buffer::lines::Lines;
+use crate::editor::view::buffer::loader::{Endings, Loader};
 use crate::editor::view::line::Line;
 use crate::editor::view::Location;
-use std::io::Error;
+#[cfg(unix)]
+use std::ffi::CString;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{BufWriter, Error, ErrorKind, IntoInnerError, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+use std::time::SystemTime;
+
+pub mod follower;
+mod highlighter;
+pub mod lines;
+mod loader;
+
+/// Files bigger than this are read in the background instead of being read at once.
+/// Binary files this big are not opened: their bytes would all be read before the first screen.
+const LAZY_LOAD_THRESHOLD: u64 = 8 * 1024 * 1024;
+/// How much of the start of a big file is looked at to tell whether it is binary
+const BINARY_SNIFF_SIZE: u64 = 8192;
+/// The control characters found in text, as `file` sees it: bell, backspace, tabs, line breaks, form feed, escape
+const TEXT_CONTROLS: [u8; 8] = [0x07, 0x08, b'\t', b'\n', 0x0b, 0x0c, b'\r', 0x1b];
+/// How many lines we go through looking for a matching bracket before giving up
+const MAX_BRACKET_SEARCH_LINES: usize = 2000;
+/// How many lines we go through looking for the brackets to highlight, which happens on every render
+const MAX_HIGHLIGHT_SEARCH_LINES: usize = 100;
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Whether the buffer appends what is written at the end of its file, see [`Buffer::follow`]
+#[derive(Default)]
+enum Following {
+    #[default]
+    No,
+    /// [`Buffer::follow`] was called while the file was loading, the follower starts once it is loaded
+    OnceLoaded,
+    Yes(Follower),
+}
 
 #[derive(Default)]
 pub struct Buffer {
-    pub lines: Vec<Line>,
+    pub lines: Lines,
+    pub file_name: Option<String>,
+    pub file_type: FileType,
+    loader: Option<Loader>,
+    highlighter: Highlighter,
+    /// Edits are refused, the view checks [`Buffer::is_read_only`] before making them
+    read_only: bool,
+    /// Counts the edits, to know if the text changed since it was saved or written to the swap file
+    revision: u64,
+    saved_revision: u64,
+    swap: Swap,
+    /// The file is copied to `file~` before being replaced by a save
+    keeps_backup: bool,
+    /// The modification time and size of the file when it was last read or written
+    disk_stamp: Option<(SystemTime, u64)>,
+    following: Following,
+    /// Lines can be edited but not added or removed, see [`Buffer::filtered`]
+    keeps_line_count: bool,
+    /// The content of a binary file, which has no lines then, see [`Buffer::make_binary`]
+    bytes: Option<Vec<u8>>,
+    /// How the lines of the file ended when it was read, they are written back the same way
+    endings: Endings,
 }
 
 impl Buffer {
@@ -16,19 +78,656 @@ impl Buffer {
         self.lines.len()
     }
 
+    /// An empty buffer for the file `file_name`, which does not exist yet: it is created on save
+    pub fn named(file_name: &str) -> Self {
+        Self {
+            file_name: Some(file_name.to_string()),
+            file_type: FileType::from_file_name(file_name),
+            ..Self::default()
+        }
+    }
+
+    /// Loads the file `file_name`.
+    /// Big files are indexed in the background: the buffer starts empty and is filled by [`Buffer::poll_loader`],
+    /// their lines being read when they are needed, see [`Lines`].
+    /// Binary files, holding null bytes or control characters, are read at once into bytes instead of lines.
+    /// Files with lines that are not UTF-8 are read-only, see [`Buffer::is_lossy`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can not be opened, is big and binary or, for small files, can not be read
     pub fn load(file_name: &str) -> Result<Self, Error> {
-        let file_content = std::fs::read_to_string(file_name)?;
-        let mut lines: Vec<Line> = Vec::new();
-        for line in file_content.lines() {
-            lines.push(Line::from(line));
+        let mut file = File::open(file_name)?;
+        let metadata = file.metadata()?;
+        let file_size = metadata.len();
+        let disk_stamp = Some((metadata.modified()?, file_size));
+        // Files the user can not write are opened read-only, rather than failing on save
+        let read_only = !is_writable(file_name, &metadata);
+        if file_size >= LAZY_LOAD_THRESHOLD {
+            if starts_binary(&mut file)? {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "The file is binary and too big to be shown",
+                ));
+            }
+            return Ok(Self {
+                lines: Lines::from_file(File::open(file_name)?),
+                file_name: Some(file_name.to_string()),
+                file_type: FileType::from_file_name(file_name),
+                loader: Some(Loader::index(file, file_size)),
+                disk_stamp,
+                read_only,
+                ..Self::default()
+            });
         }
-        Ok(Self { lines })
+        let buffer = Self::read(file)?;
+        Ok(Self {
+            file_name: Some(file_name.to_string()),
+            file_type: FileType::from_file_name(file_name),
+            disk_stamp,
+            read_only: read_only || buffer.read_only,
+            ..buffer
+        })
+    }
+
+    /// Loads the file again, the text becomes the one on the disk
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer has no file or if [`Buffer::load`] fails
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let Some(file_name) = &self.file_name else {
+            return Err(Error::new(ErrorKind::NotFound, "The buffer has no file"));
+        };
+        let mut buffer = Self::load(file_name)?;
+        buffer.read_only = self.read_only;
+        buffer.keeps_backup = self.keeps_backup;
+        buffer.swap = std::mem::take(&mut self.swap);
+        buffer.revision = self.revision.wrapping_add(1);
+        *self = buffer;
+        self.saved();
+        Ok(())
+    }
+
+    /// The file was written by another program since it was read or saved.
+    /// A file that was deleted does not count, saving creates it again, nor does a followed file.
+    pub fn is_changed_on_disk(&self) -> bool {
+        let Some(file_name) = &self.file_name else {
+            return false;
+        };
+        if self.is_following() {
+            return false;
+        }
+        self.disk_stamp
+            .is_some_and(|stamp| read_stamp(file_name).is_ok_and(|disk_stamp| disk_stamp != stamp))
+    }
+
+    /// Keeps the text as it is although the file changed on disk: the next save writes over it
+    pub fn ignore_disk_changes(&mut self) {
+        if let Some(file_name) = &self.file_name {
+            self.disk_stamp = read_stamp(file_name).ok();
+        }
+    }
+
+    /// Reads all of `reader` into a buffer without a file, holding its bytes if they are not text.
+    /// The buffer is read-only if some lines are not UTF-8, see [`Buffer::is_lossy`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `reader` fails
+    pub fn read(mut reader: impl Read) -> Result<Self, Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let endings = Endings::of(&bytes);
+        Ok(match text_lines(bytes) {
+            Ok(lines) => Self {
+                read_only: lines.is_lossy(),
+                lines,
+                endings,
+                ..Self::default()
+            },
+            Err(bytes) => Self {
+                bytes: Some(bytes),
+                ..Self::default()
+            },
+        })
+    }
+
+    /// Starts reading `source` in the background into a buffer without a file, like a big file.
+    /// This is used for text piped to the editor, which can keep coming for a long time.
+    pub fn stream<R: Read + Send + 'static>(source: R) -> Self {
+        Self {
+            loader: Some(Loader::spawn(source, 0)),
+            ..Self::default()
+        }
+    }
+
+    /// Writes the lines to the file of the buffer, see [`Buffer::write_lines`]
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer has no file, is still loading or lossy or if the file can not be written
+    pub fn save(&mut self) -> Result<(), Error> {
+        let Some(file_name) = &self.file_name else {
+            return Err(Error::new(ErrorKind::NotFound, "The buffer has no file"));
+        };
+        if self.is_changed_on_disk() {
+            return Err(Error::other(format!(
+                "{file_name} changed on disk since it was read"
+            )));
+        }
+        self.write_to(file_name)?;
+        self.disk_stamp = read_stamp(file_name).ok();
+        self.saved();
+        Ok(())
+    }
+
+    /// Writes the buffer to `file_name`, which becomes its file if it could be written
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer is still loading or lossy or if the file can not be written
+    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
+        self.write_to(file_name)?;
+        self.disk_stamp = read_stamp(file_name).ok();
+        self.file_name = Some(file_name.to_string());
+        self.file_type = FileType::from_file_name(file_name);
+        self.highlighter.invalidate_from(0);
+        self.saved();
+        Ok(())
+    }
+
+    /// The text is in its file, the swap file is not needed anymore
+    fn saved(&mut self) {
+        self.saved_revision = self.revision;
+        let _ = self.swap.remove();
+    }
+
+    /// Writes the text to a temporary file next to the file, then renames it over the file,
+    /// so the file is never left half written. Symbolic links are followed to write the file they point to,
+    /// its permissions are kept and, if backups are on, it is copied to `file~` before being replaced.
+    fn write_to(&self, file_name: &str) -> Result<(), Error> {
+        if self.is_loading() {
+            return Err(Error::new(
+                ErrorKind::WouldBlock,
+                "The buffer is still loading",
+            ));
+        }
+        if self.is_lossy() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The text is not valid UTF-8, saving would replace its invalid bytes",
+            ));
+        }
+        let target = follow_links(Path::new(file_name))?;
+        let name = target
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "The file name is not valid"))?;
+        let temporary = target.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+        let result = self.write_temporary(&temporary, &target).and_then(|()| {
+            if self.keeps_backup && target.exists() {
+                let backup = target.with_file_name(format!("{name}~"));
+                fs::copy(&target, &backup)
+                    .map_err(|error| context(&error, "Could not back up to", &backup))?;
+            }
+            fs::rename(&temporary, &target)
+                .map_err(|error| context(&error, "Could not replace", &target))
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+            return result;
+        }
+        // The rename is only on the disk once the directory is
+        #[cfg(unix)]
+        if let Some(directory) = target
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            let _ = File::open(directory).and_then(|directory| directory.sync_all());
+        }
+        Ok(())
+    }
+
+    /// Writes the lines, see [`Buffer::write_lines`], or the bytes of a binary buffer,
+    /// to the new file `temporary` with the permissions of `target`
+    fn write_temporary(&self, temporary: &Path, target: &Path) -> Result<(), Error> {
+        let permissions = match fs::metadata(target) {
+            Ok(metadata) => Some(metadata.permissions()),
+            Err(error) if error.kind() == ErrorKind::NotFound => None,
+            Err(error) => return Err(context(&error, "Could not read", target)),
+        };
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        // The text is never readable by more users than the file, even before the permissions are copied
+        #[cfg(unix)]
+        if let Some(permissions) = &permissions {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(permissions.mode());
+        }
+        let file = options
+            .open(temporary)
+            .map_err(|error| context(&error, "Could not create", temporary))?;
+        let mut writer = BufWriter::new(file);
+        match &self.bytes {
+            Some(bytes) => writer.write_all(bytes),
+            None => self.write_lines(&mut writer),
+        }
+        .and_then(|()| writer.into_inner().map_err(IntoInnerError::into_error))
+        .and_then(|file| file.sync_all())
+        .map_err(|error| context(&error, "Could not write", temporary))?;
+        if let Some(permissions) = permissions {
+            fs::set_permissions(temporary, permissions).map_err(|error| {
+                context(
+                    &error,
+                    "Could not give the permissions of the file to",
+                    temporary,
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Writes every line to `writer`, followed by the line break the file had when it was read.
+    /// The last line only gets one if the file ended with a line break.
+    fn write_lines(&self, writer: &mut impl Write) -> Result<(), Error> {
+        let line_break = self.endings.line_break();
+        let last_index = self.lines.len().saturating_sub(1);
+        self.lines
+            .iter_uncached()
+            .enumerate()
+            .try_for_each(|(index, line)| {
+                if index == last_index && !self.endings.final_newline {
+                    write!(writer, "{line}")
+                } else {
+                    write!(writer, "{line}{line_break}")
+                }
+            })
+    }
+
+    /// Appends the lines read in the background since the last call.
+    /// A file with lines that are not UTF-8 stays read-only once loaded, see [`Buffer::is_lossy`].
+    /// Returns `true` if the buffer changed.
+    pub fn poll_loader(&mut self) -> bool {
+        let Some(loader) = self.loader.as_mut() else {
+            return false;
+        };
+        let received = loader.receive_lines(&mut self.lines);
+        let changed = received || loader.is_finished();
+        if loader.is_finished() {
+            let bytes_read = loader.bytes_read();
+            self.endings = loader.endings();
+            self.loader = None;
+            self.read_only = self.read_only || self.is_lossy();
+            if matches!(self.following, Following::OnceLoaded) {
+                self.start_follower(bytes_read);
+            }
+        }
+        changed
+    }
+
+    /// A buffer is read-only as long as it is loading
+    pub fn is_loading(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    /// Edits are refused while the buffer is loading or following its file, or if it was opened read-only
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || self.is_loading() || self.is_following()
+    }
+
+    pub fn is_following(&self) -> bool {
+        !matches!(self.following, Following::No)
+    }
+
+    /// Appends what is written at the end of the file from now on, like `tail -F`, see [`Buffer::poll_follower`].
+    /// The buffer is read-only while it follows the file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer has no file, is modified or binary or if the file can not be read
+    pub fn follow(&mut self) -> Result<(), Error> {
+        if self.file_name.is_none() {
+            return Err(Error::new(ErrorKind::NotFound, "The buffer has no file"));
+        }
+        if self.is_modified() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Save the buffer before following its file",
+            ));
+        }
+        if self.is_binary() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Binary files can not be followed",
+            ));
+        }
+        if self.is_loading() {
+            self.following = Following::OnceLoaded;
+            return Ok(());
+        }
+        if self.is_changed_on_disk() {
+            self.reload()?;
+        }
+        let size = self.disk_stamp.map_or(0, |(_, size)| size);
+        self.start_follower(size);
+        if !self.is_following() {
+            return Err(Error::new(ErrorKind::NotFound, "The file can not be read"));
+        }
+        Ok(())
+    }
+
+    fn start_follower(&mut self, offset: u64) {
+        let last_line = self
+            .lines
+            .last()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        self.following = self
+            .file_name
+            .as_deref()
+            .and_then(|file_name| Follower::new(file_name, offset, &last_line).ok())
+            .map_or(Following::No, Following::Yes);
+    }
+
+    pub fn stop_following(&mut self) {
+        self.following = Following::No;
+    }
+
+    /// Takes the lines written to the followed file since the last call, or all its lines if it was truncated
+    /// or replaced. The text stays unmodified, as it is the one of the file.
+    /// Returns how the file changed, or `None` if it did not.
+    pub fn poll_follower(&mut self) -> Option<Growth> {
+        let Following::Yes(follower) = &mut self.following else {
+            return None;
+        };
+        let update = follower.poll().ok()??;
+        let first_line_index = if update.growth == Growth::Appended {
+            if update.continues_last {
+                self.lines.pop();
+            }
+            self.lines.len()
+        } else {
+            self.lines.clear();
+            0
+        };
+        self.lines.extend(update.lines);
+        self.edited_from(first_line_index);
+        self.saved();
+        if let Some(file_name) = &self.file_name {
+            self.disk_stamp = read_stamp(file_name).ok();
+        }
+        Some(update.growth)
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Allows edits if the buffer was read-only and refuses them otherwise, a loading buffer stays read-only
+    pub fn toggle_read_only(&mut self) {
+        self.read_only = !self.read_only;
+    }
+
+    /// Returns a buffer with the lines at `line_indexes`, whose lines can be edited but not added or removed,
+    /// to be copied back with [`Buffer::copy_lines_from`]
+    pub fn filtered(&self, line_indexes: &[usize]) -> Self {
+        Self {
+            lines: line_indexes
+                .iter()
+                .filter_map(|index| self.lines.get(*index).cloned())
+                .collect(),
+            file_type: self.file_type,
+            read_only: self.read_only,
+            keeps_line_count: true,
+            ..Self::default()
+        }
+    }
+
+    /// Replaces the lines at `line_indexes` with the lines of `filtered` that differ from them
+    pub fn copy_lines_from(&mut self, filtered: &Self, line_indexes: &[usize]) {
+        for (line, index) in filtered.lines.iter().zip(line_indexes) {
+            let is_edited = self
+                .lines
+                .get(*index)
+                .is_some_and(|target| target.to_string() != line.to_string());
+            if let Some(target) = self.lines.get_mut(*index).filter(|_| is_edited) {
+                *target = line.clone();
+                self.edited_from(*index);
+            }
+        }
+    }
+
+    pub fn keeps_line_count(&self) -> bool {
+        self.keeps_line_count
+    }
+
+    /// Some lines were not UTF-8 and had their invalid bytes replaced: saving would change them
+    pub fn is_lossy(&self) -> bool {
+        self.lines.is_lossy()
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.bytes.is_some()
+    }
+
+    /// The content of a binary buffer
+    pub fn bytes(&self) -> Option<&[u8]> {
+        self.bytes.as_deref()
+    }
+
+    /// Overwrites the byte at `offset` of a binary buffer.
+    /// Returns `false` if the buffer is read-only, is not binary or has no such byte.
+    pub fn set_byte(&mut self, offset: usize, byte: u8) -> bool {
+        if self.is_read_only() {
+            return false;
+        }
+        let Some(old_byte) = self.bytes.as_mut().and_then(|bytes| bytes.get_mut(offset)) else {
+            return false;
+        };
+        *old_byte = byte;
+        self.edited_from(0);
+        true
+    }
+
+    /// Turns the lines into the bytes a save would write, to edit them one by one.
+    /// This is not an edit: the buffer stays modified or not.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer is loading, following its file or lossy
+    pub fn make_binary(&mut self) -> Result<(), Error> {
+        if self.is_loading() {
+            return Err(Error::new(
+                ErrorKind::WouldBlock,
+                "The file is still loading",
+            ));
+        }
+        if self.is_following() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Stop following the file to see its bytes",
+            ));
+        }
+        if self.is_binary() {
+            return Ok(());
+        }
+        if self.is_lossy() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The bytes that are not UTF-8 were replaced, they can not be shown",
+            ));
+        }
+        let mut bytes = Vec::new();
+        self.write_lines(&mut bytes)?;
+        self.lines.clear();
+        self.bytes = Some(bytes);
+        self.highlighter.invalidate_from(0);
+        Ok(())
+    }
+
+    /// Turns the bytes of a binary buffer back into lines, see [`Buffer::make_binary`]
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the bytes are binary, see [`is_binary`], or are not UTF-8
+    pub fn make_text(&mut self) -> Result<(), Error> {
+        let Some(bytes) = self.bytes.take() else {
+            return Ok(());
+        };
+        match String::from_utf8(bytes) {
+            Ok(text) if !is_binary(text.as_bytes()) => {
+                self.endings = Endings::of(text.as_bytes());
+                self.lines = text.lines().map(Line::from).collect();
+                self.highlighter.invalidate_from(0);
+                Ok(())
+            }
+            result => {
+                self.bytes =
+                    Some(result.map_or_else(FromUtf8Error::into_bytes, String::into_bytes));
+                Err(Error::new(ErrorKind::InvalidData, "The bytes are not text"))
+            }
+        }
+    }
+
+    /// Changes with every edit
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// The text changed since it was loaded or saved
+    pub fn is_modified(&self) -> bool {
+        self.revision != self.saved_revision
+    }
+
+    /// Every edit goes through here: the highlighting is computed again from `line_index` and the revision changes
+    fn edited_from(&mut self, line_index: usize) {
+        self.highlighter.invalidate_from(line_index);
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    /// Gives the text to the swap file if it changed since the last call, to be written by [`Buffer::write_swap`].
+    /// Only modified buffers have a swap file, in the cache directory for the ones without a file.
+    /// Binary buffers have none as it holds text, and big files read when shown have none either:
+    /// it would hold a copy of the whole file.
+    pub fn update_swap(&mut self) {
+        if self.is_binary() || self.lines.is_indexed() {
+            return;
+        }
+        if self.is_modified() {
+            let lines = &self.lines;
+            let file_name = self.file_name.as_deref().unwrap_or_default();
+            self.swap.update(file_name, self.revision, || {
+                lines.iter_uncached().fold(String::new(), |mut text, line| {
+                    text.push_str(&line.to_string());
+                    text.push('\n');
+                    text
+                })
+            });
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Swap::write`] fails
+    pub fn write_swap(&mut self) -> Result<(), Error> {
+        self.swap.write()
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if [`Swap::remove`] fails
+    pub fn remove_swap(&mut self) -> Result<(), Error> {
+        self.swap.remove()
+    }
+
+    /// Returns the swap file left for the file of the buffer by an editor that did not quit cleanly,
+    /// or for a buffer without a file if it has none, see [`Swap::check`].
+    /// It is only returned once, after the buffer is loaded, and never for read-only buffers.
+    pub fn check_swap(&mut self) -> Option<PathBuf> {
+        if self.is_read_only() {
+            return None;
+        }
+        self.swap
+            .check(self.file_name.as_deref().unwrap_or_default())
+    }
+
+    /// Where the text of a buffer without a file was last written, to be found after the editor stops
+    pub fn unnamed_swap_path(&self) -> Option<PathBuf> {
+        if self.file_name.is_some() {
+            return None;
+        }
+        self.swap.path()
+    }
+
+    /// Replaces the text with the one of the swap file `path`, which becomes the swap file of the buffer
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer is read-only or the swap file can not be read
+    pub fn recover(&mut self, path: &Path) -> Result<(), Error> {
+        if self.is_read_only() {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "The buffer is read-only",
+            ));
+        }
+        let content = fs::read_to_string(path)?;
+        self.lines = content.lines().map(Line::from).collect();
+        self.bytes = None;
+        self.edited_from(0);
+        self.swap.adopt(path);
+        Ok(())
+    }
+
+    /// Returns a buffer with the differences between the text and the one of the file `path`, see [`diff::unified`]
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer is binary or if the file can not be read
+    pub fn diff_with(&self, path: &Path) -> Result<Self, Error> {
+        if self.is_binary() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Binary files can not be compared",
+            ));
+        }
+        let old: Vec<String> = self
+            .lines
+            .iter_uncached()
+            .map(|line| line.to_string())
+            .collect();
+        let new: Vec<String> = fs::read_to_string(path)?
+            .lines()
+            .map(ToString::to_string)
+            .collect();
+        Ok(Self {
+            lines: diff::unified(&old, &new)
+                .iter()
+                .map(|line| Line::from(line))
+                .collect(),
+            read_only: true,
+            ..Self::default()
+        })
+    }
+
+    pub fn set_keeps_backup(&mut self, keeps_backup: bool) {
+        self.keeps_backup = keeps_backup;
+    }
+
+    pub fn load_progress(&self) -> Option<u8> {
+        self.loader.as_ref().and_then(Loader::progress)
     }
 
     pub fn insert_char(&mut self, character: char, at: &Location) {
-        if at.line_index > self.lines.len() {
+        let adds_line = at.line_index == self.lines.len();
+        if self.is_read_only()
+            || at.line_index > self.lines.len()
+            || (adds_line && self.keeps_line_count)
+        {
             return;
         }
+        self.edited_from(at.line_index);
         if at.line_index == self.lines.len() {
             self.lines.push(Line::from(&character.to_string()));
         } else if let Some(line) = self.lines.get_mut(at.line_index) {
@@ -37,27 +736,951 @@ impl Buffer {
     }
 
     pub fn delete(&mut self, at: &Location) {
-        if let Some(line) = self.lines.get(at.line_index) {
-            if at.grapheme_index >= line.grapheme_count()
-                && self.lines.len() > at.line_index.saturating_add(1)
-            {
-                let next_line = self.lines.remove(at.line_index.saturating_add(1));
-                //We checked that the line at line_index existed
-                #[allow(clippy::indexing_slicing)]
-                self.lines[at.line_index].append(&next_line);
-            } else if at.grapheme_index < line.grapheme_count() {
-                //We checked that the line at line_index existed
-                #[allow(clippy::indexing_slicing)]
-                self.lines[at.line_index].delete(at.grapheme_index);
+        if self.is_read_only() {
+            return;
+        }
+        let Some(grapheme_count) = self.lines.get(at.line_index).map(Line::grapheme_count) else {
+            return;
+        };
+        if at.grapheme_index >= grapheme_count
+            && self.lines.len() > at.line_index.saturating_add(1)
+            && !self.keeps_line_count
+        {
+            self.edited_from(at.line_index);
+            let next_line = self.lines.remove(at.line_index.saturating_add(1));
+            if let Some(line) = self.lines.get_mut(at.line_index) {
+                line.append(&next_line);
+            }
+        } else if at.grapheme_index < grapheme_count {
+            self.edited_from(at.line_index);
+            if let Some(line) = self.lines.get_mut(at.line_index) {
+                line.delete(at.grapheme_index);
+            }
+        }
+    }
+
+    /// Deletes the text between `start` and `end`, `start` must come before `end`.
+    /// Lines between them are removed and the remainders of the first and last lines are joined.
+    pub fn delete_range(&mut self, start: &Location, end: &Location) {
+        if self.is_read_only()
+            || start.line_index >= self.lines.len()
+            || end.line_index >= self.lines.len()
+        {
+            return;
+        }
+        if self.keeps_line_count && start.line_index != end.line_index {
+            return;
+        }
+        self.edited_from(start.line_index);
+        let suffix = self
+            .lines
+            .get_mut(end.line_index)
+            .map(|line| line.split_at(end.grapheme_index))
+            .unwrap_or_default();
+        if let Some(line) = self.lines.get_mut(start.line_index) {
+            line.split_at(start.grapheme_index);
+            line.append(&suffix);
+        }
+        self.lines
+            .remove_range(start.line_index.saturating_add(1)..=end.line_index);
+    }
+
+    /// Splits the line at `at` and returns where the caret goes.
+    /// The new line keeps the indentation of the split line, one more level after an opening bracket or colon
+    /// depending on the file type. A line left with only whitespace is emptied.
+    pub fn insert_line(&mut self, at: &Location) -> Location {
+        if self.is_read_only() || self.keeps_line_count {
+            return at.clone();
+        }
+        if self.lines.get(at.line_index).is_none() {
+            return at.clone();
+        }
+        let file_type = self.file_type;
+        self.edited_from(at.line_index);
+        let Some(line) = self.lines.get_mut(at.line_index) else {
+            return at.clone();
+        };
+        let tail = line.split_at(at.grapheme_index).to_string();
+        let tail = tail.trim_start();
+        let head = line.to_string();
+        if head.trim().is_empty() {
+            *line = Line::default();
+        }
+        let indent = indentation(&head);
+        let mut new_indent = indent.clone();
+        let mut new_lines = Vec::new();
+        if file_type.opens_block(&head) {
+            new_indent.push_str(INDENT);
+            let closes_block = head
+                .trim_end()
+                .chars()
+                .last()
+                .and_then(|opening| file_type.closing_bracket(opening))
+                .is_some_and(|closing| tail.starts_with(closing));
+            if closes_block {
+                new_lines.push(Line::from(&new_indent));
+                new_lines.push(Line::from(&format!("{indent}{tail}")));
             }
         }
+        if new_lines.is_empty() {
+            new_lines.push(Line::from(&format!("{new_indent}{tail}")));
+        }
+        let new_line_index = at.line_index.saturating_add(1);
+        self.lines.insert_lines(new_line_index, new_lines);
+        Location {
+            grapheme_index: Line::from(&new_indent).grapheme_count(),
+            line_index: new_line_index,
+        }
     }
 
-    pub fn insert_line(&mut self, at: &Location) {
-        if let Some(line_to_split) = self.lines.get_mut(at.line_index) {
-            let line_sliced = line_to_split.split_at(at.grapheme_index);
-            self.lines
-                .insert(at.line_index.saturating_add(1), line_sliced);
+    /// Inserts a line before `line_index` with the same indentation, and returns where the caret goes
+    pub fn open_line_above(&mut self, line_index: usize) -> Location {
+        let line_index = line_index.min(self.lines.len());
+        if self.is_read_only() || self.keeps_line_count {
+            return Location {
+                grapheme_index: 0,
+                line_index,
+            };
         }
+        let indent = self
+            .lines
+            .get(line_index)
+            .map(|line| indentation(&line.to_string()))
+            .unwrap_or_default();
+        let line = Line::from(&indent);
+        let grapheme_index = line.grapheme_count();
+        self.edited_from(line_index);
+        self.lines.insert(line_index, line);
+        Location {
+            grapheme_index,
+            line_index,
+        }
+    }
+
+    /// Inserts `text`, which can span several lines separated by `\n`, at `at`.
+    /// Returns the location of the end of the inserted text.
+    pub fn insert_text(&mut self, at: &Location, text: &str) -> Location {
+        let adds_lines = text.contains('\n') || at.line_index == self.lines.len();
+        if self.is_read_only()
+            || at.line_index > self.lines.len()
+            || (adds_lines && self.keeps_line_count)
+        {
+            return at.clone();
+        }
+        self.edited_from(at.line_index);
+        if at.line_index == self.lines.len() {
+            self.lines.push(Line::default());
+        }
+        let Some(line) = self.lines.get_mut(at.line_index) else {
+            return at.clone();
+        };
+        let tail = line.split_at(at.grapheme_index);
+        let mut chunks = text.split('\n');
+        line.append(&Line::from(chunks.next().unwrap_or_default()));
+        let mut new_lines: Vec<Line> = chunks.map(Line::from).collect();
+        let end = if let Some(last) = new_lines.last_mut() {
+            let grapheme_index = last.grapheme_count();
+            last.append(&tail);
+            Location {
+                grapheme_index,
+                line_index: at.line_index.saturating_add(new_lines.len()),
+            }
+        } else {
+            let grapheme_index = line.grapheme_count();
+            line.append(&tail);
+            Location {
+                grapheme_index,
+                line_index: at.line_index,
+            }
+        };
+        let next_line_index = at.line_index.saturating_add(1);
+        self.lines.insert_lines(next_line_index, new_lines);
+        end
+    }
+
+    /// Returns the text between `start` and `end`, lines being separated by `\n`
+    pub fn text_in_range(&self, start: &Location, end: &Location) -> String {
+        let mut text = String::new();
+        for line_index in start.line_index..=end.line_index {
+            let Some(line) = self.lines.get(line_index) else {
+                break;
+            };
+            let from = if line_index == start.line_index {
+                start.grapheme_index
+            } else {
+                0
+            };
+            let to = if line_index == end.line_index {
+                end.grapheme_index
+            } else {
+                line.grapheme_count()
+            };
+            text.extend(line.graphemes().skip(from).take(to.saturating_sub(from)));
+            if line_index < end.line_index {
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Returns the start of the first occurrence of `text` at or after `from`, going on from the start of the buffer
+    /// after its end. `text` must not span several lines.
+    pub fn find_next(&self, text: &str, from: &Location) -> Option<Location> {
+        let text = Line::from(text);
+        let needle: Vec<&str> = text.graphemes().collect();
+        if needle.is_empty() {
+            return None;
+        }
+        let height = self.lines.len();
+        // The line of `from` is searched twice: after `from` first, then before it once every other line is searched
+        for offset in 0..=height {
+            let line_index = from.line_index.saturating_add(offset).checked_rem(height)?;
+            let Some(line) = self.lines.get_uncached(line_index) else {
+                continue;
+            };
+            let graphemes: Vec<&str> = line.graphemes().collect();
+            let candidates = match offset {
+                0 => from.grapheme_index..graphemes.len(),
+                _ if offset == height => 0..from.grapheme_index,
+                _ => 0..graphemes.len(),
+            };
+            for grapheme_index in candidates {
+                let is_match = graphemes
+                    .get(grapheme_index..grapheme_index.saturating_add(needle.len()))
+                    .is_some_and(|window| window.iter().eq(needle.iter()));
+                if is_match {
+                    return Some(Location {
+                        grapheme_index,
+                        line_index,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Removes one level of indentation before `at` if `character` closes a block and there is only whitespace before `at`.
+    /// Returns the number of graphemes removed.
+    pub fn outdent_before(&mut self, character: char, at: &Location) -> usize {
+        if self.is_read_only() || !self.file_type.is_closing_bracket(character) {
+            return 0;
+        }
+        let Some(line) = self.lines.get_mut(at.line_index) else {
+            return 0;
+        };
+        let mut head = line.clone();
+        let tail = head.split_at(at.grapheme_index);
+        let head = head.to_string();
+        if head.is_empty() || !head.trim().is_empty() {
+            return 0;
+        }
+        let mut new_head = head.clone();
+        if new_head.ends_with('\t') {
+            new_head.pop();
+        } else {
+            for _ in 0..INDENT.len() {
+                if !new_head.ends_with(' ') {
+                    break;
+                }
+                new_head.pop();
+            }
+        }
+        *line = Line::from(&format!("{new_head}{tail}"));
+        self.edited_from(at.line_index);
+        head.len().saturating_sub(new_head.len())
+    }
+
+    /// Returns where moving one word to the left of `at` goes: the start of the previous word,
+    /// or the end of the previous line when `at` is at the start of a line.
+    pub fn word_start_before(&self, at: &Location) -> Location {
+        let Some(line) = self.lines.get(at.line_index) else {
+            return at.clone();
+        };
+        if at.grapheme_index == 0 {
+            if let Some(previous_line_index) = at.line_index.checked_sub(1) {
+                return Location {
+                    grapheme_index: self
+                        .lines
+                        .get(previous_line_index)
+                        .map_or(0, Line::grapheme_count),
+                    line_index: previous_line_index,
+                };
+            }
+        }
+        Location {
+            grapheme_index: line.word_start_before(at.grapheme_index),
+            line_index: at.line_index,
+        }
+    }
+
+    /// Returns where moving one word to the right of `at` goes: the end of the next word,
+    /// or the start of the next line when `at` is at the end of a line.
+    pub fn word_end_after(&self, at: &Location) -> Location {
+        let Some(line) = self.lines.get(at.line_index) else {
+            return at.clone();
+        };
+        let next_line_index = at.line_index.saturating_add(1);
+        if at.grapheme_index >= line.grapheme_count() && next_line_index < self.lines.len() {
+            return Location {
+                grapheme_index: 0,
+                line_index: next_line_index,
+            };
+        }
+        Location {
+            grapheme_index: line.word_end_after(at.grapheme_index),
+            line_index: at.line_index,
+        }
+    }
+
+    /// Returns the start of the next word after `at`, which can be on one of the next lines.
+    /// Empty lines count as words.
+    pub fn word_start_after(&self, at: &Location) -> Location {
+        let Some(line) = self.lines.get(at.line_index) else {
+            return at.clone();
+        };
+        if let Some(grapheme_index) = line.word_start_after(at.grapheme_index) {
+            return Location {
+                grapheme_index,
+                line_index: at.line_index,
+            };
+        }
+        let next_line_index = at.line_index.saturating_add(1);
+        match self.lines.get(next_line_index) {
+            Some(next_line) => Location {
+                grapheme_index: next_line.first_word_start(),
+                line_index: next_line_index,
+            },
+            None => Location {
+                grapheme_index: line.grapheme_count(),
+                line_index: at.line_index,
+            },
+        }
+    }
+
+    /// Returns, for every grapheme of the line, the bracket it is if it is one.
+    /// Brackets in strings and comments are ignored for file types the highlighter knows about.
+    fn brackets_of_line(&mut self, line_index: usize) -> Vec<Option<char>> {
+        let literal = self
+            .highlighter
+            .literal_graphemes(self.file_type, &self.lines, line_index);
+        let Some(line) = self.lines.get(line_index) else {
+            return Vec::new();
+        };
+        line.graphemes()
+            .zip(literal)
+            .map(|(grapheme, is_literal)| {
+                let mut characters = grapheme.chars();
+                match (characters.next(), characters.next()) {
+                    (Some(character), None) if !is_literal && is_bracket(character) => {
+                        Some(character)
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the location of the bracket matching the one at `at`, searching across lines
+    /// but at most `max_lines` lines away
+    fn matching_bracket(&mut self, at: &Location, max_lines: usize) -> Option<Location> {
+        let brackets = self.brackets_of_line(at.line_index);
+        let bracket = (*brackets.get(at.grapheme_index)?)?;
+        let (opening, closing) = BRACKETS
+            .into_iter()
+            .find(|(opening, closing)| *opening == bracket || *closing == bracket)?;
+        let mut depth: usize = 0;
+        let mut find = |line_index: usize, brackets: &[Option<char>], graphemes: &[usize]| {
+            for grapheme_index in graphemes {
+                match brackets.get(*grapheme_index).copied().flatten() {
+                    Some(found) if found == bracket => depth = depth.saturating_add(1),
+                    Some(found) if found == opening || found == closing => {
+                        depth = depth.saturating_sub(1);
+                        if depth == 0 {
+                            return Some(Location {
+                                grapheme_index: *grapheme_index,
+                                line_index,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            None
+        };
+        if bracket == opening {
+            let last_line = at
+                .line_index
+                .saturating_add(max_lines)
+                .min(self.lines.len());
+            for line_index in at.line_index..last_line {
+                let brackets = if line_index == at.line_index {
+                    brackets.clone()
+                } else {
+                    self.brackets_of_line(line_index)
+                };
+                let start = if line_index == at.line_index {
+                    at.grapheme_index
+                } else {
+                    0
+                };
+                let graphemes: Vec<usize> = (start..brackets.len()).collect();
+                if let Some(location) = find(line_index, &brackets, &graphemes) {
+                    return Some(location);
+                }
+            }
+        } else {
+            let first_line = at.line_index.saturating_sub(max_lines);
+            for line_index in (first_line..=at.line_index).rev() {
+                let brackets = if line_index == at.line_index {
+                    brackets.clone()
+                } else {
+                    self.brackets_of_line(line_index)
+                };
+                let end = if line_index == at.line_index {
+                    at.grapheme_index.saturating_add(1)
+                } else {
+                    brackets.len()
+                };
+                let graphemes: Vec<usize> = (0..end).rev().collect();
+                if let Some(location) = find(line_index, &brackets, &graphemes) {
+                    return Some(location);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the innermost opening bracket before `at` that is not closed before `at`,
+    /// at most `max_lines` lines before it
+    fn enclosing_opening_bracket(&mut self, at: &Location, max_lines: usize) -> Option<Location> {
+        let mut depths = [0usize; BRACKETS.len()];
+        let first_line = at.line_index.saturating_sub(max_lines);
+        for line_index in (first_line..=at.line_index.min(self.lines.len())).rev() {
+            let brackets = self.brackets_of_line(line_index);
+            let end = if line_index == at.line_index {
+                at.grapheme_index.min(brackets.len())
+            } else {
+                brackets.len()
+            };
+            for grapheme_index in (0..end).rev() {
+                let Some(bracket) = brackets.get(grapheme_index).copied().flatten() else {
+                    continue;
+                };
+                for ((opening, closing), depth) in BRACKETS.iter().zip(depths.iter_mut()) {
+                    if bracket == *closing {
+                        *depth = depth.saturating_add(1);
+                    } else if bracket == *opening {
+                        if *depth == 0 {
+                            return Some(Location {
+                                grapheme_index,
+                                line_index,
+                            });
+                        }
+                        *depth = depth.saturating_sub(1);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the pair of brackets to highlight for a caret at `caret`: the bracket under the caret,
+    /// or the one just before it, and its match. Otherwise, the innermost pair around the caret.
+    pub fn bracket_pair(&mut self, caret: &Location) -> Option<(Location, Location)> {
+        self.bracket_pair_within(caret, MAX_BRACKET_SEARCH_LINES)
+    }
+
+    /// Returns the pair of brackets to highlight like [`Buffer::bracket_pair`], only looking at the lines
+    /// near the caret: this is done on every render, and going through thousands of lines each time is too slow
+    pub fn highlighted_bracket_pair(&mut self, caret: &Location) -> Option<(Location, Location)> {
+        self.bracket_pair_within(caret, MAX_HIGHLIGHT_SEARCH_LINES)
+    }
+
+    fn bracket_pair_within(
+        &mut self,
+        caret: &Location,
+        max_lines: usize,
+    ) -> Option<(Location, Location)> {
+        let before = caret
+            .grapheme_index
+            .checked_sub(1)
+            .map(|grapheme_index| Location {
+                grapheme_index,
+                line_index: caret.line_index,
+            });
+        for bracket in [Some(caret.clone()), before].into_iter().flatten() {
+            if let Some(matching) = self.matching_bracket(&bracket, max_lines) {
+                return Some((bracket, matching));
+            }
+        }
+        let opening = self.enclosing_opening_bracket(caret, max_lines)?;
+        let closing = self.matching_bracket(&opening, max_lines)?;
+        Some((opening, closing))
+    }
+}
+
+/// Returns the file `path` points to, following symbolic links even if the file they point to does not exist yet
+fn follow_links(path: &Path) -> Result<PathBuf, Error> {
+    /// Like the limit of most systems, to stop on links pointing to each other
+    const MAX_LINKS: usize = 40;
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_LINKS {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&path)
+                    .map_err(|error| context(&error, "Could not follow", &path))?;
+                path = path
+                    .parent()
+                    .map_or_else(|| link.clone(), |parent| parent.join(&link));
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(Error::other(format!(
+        "Could not follow {}: too many symbolic links",
+        path.display()
+    )))
+}
+
+/// Tells whether the user can write `file_name`. It is not opened for writing to find out:
+/// the programs watching the file would be told it was written.
+#[cfg(unix)]
+fn is_writable(file_name: &str, _metadata: &Metadata) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = CString::new(Path::new(file_name).as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a C string that lives until `access` returns, which only reads it
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+/// Tells whether the user can write `file_name`, from the permissions in `metadata`
+#[cfg(not(unix))]
+fn is_writable(_file_name: &str, metadata: &Metadata) -> bool {
+    !metadata.permissions().readonly()
+}
+
+/// Returns the modification time and size of `file_name`
+fn read_stamp(file_name: &str) -> Result<(SystemTime, u64), Error> {
+    let metadata = fs::metadata(file_name)?;
+    Ok((metadata.modified()?, metadata.len()))
+}
+
+/// Returns the lines of `bytes`, or gives them back if they are binary, see [`is_binary`].
+/// The bytes of the lines that are not UTF-8 are replaced, the lines are lossy then.
+fn text_lines(bytes: Vec<u8>) -> Result<Lines, Vec<u8>> {
+    if is_binary(&bytes) {
+        return Err(bytes);
+    }
+    Ok(match String::from_utf8(bytes) {
+        Ok(text) => text.lines().map(Line::from).collect(),
+        Err(error) => {
+            let mut lines: Lines = String::from_utf8_lossy(error.as_bytes())
+                .lines()
+                .map(Line::from)
+                .collect();
+            lines.mark_lossy();
+            lines
+        }
+    })
+}
+
+/// Tells whether `bytes` are binary: holding null bytes or control characters text does not have.
+/// Text in another encoding than UTF-8 is not binary.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .any(|byte| byte.is_ascii_control() && !TEXT_CONTROLS.contains(byte))
+}
+
+/// Tells whether the start of `file` is binary, see [`is_binary`], then goes back to the start
+fn starts_binary(file: &mut File) -> Result<bool, Error> {
+    let mut start = Vec::new();
+    Read::by_ref(file)
+        .take(BINARY_SNIFF_SIZE)
+        .read_to_end(&mut start)?;
+    file.rewind()?;
+    Ok(is_binary(&start))
+}
+
+/// Tells which step of saving failed, and on which file
+fn context(error: &Error, step: &str, path: &Path) -> Error {
+    Error::new(error.kind(), format!("{step} {}: {error}", path.display()))
+}
+
+/// Returns the spaces and tabs at the start of `text`
+fn indentation(text: &str) -> String {
+    text.chars()
+        .take_while(|character| *character == ' ' || *character == '\t')
+        .collect()
+}
+
+fn is_bracket(character: char) -> bool {
+    BRACKETS
+        .iter()
+        .any(|(opening, closing)| character == *opening || character == *closing)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::test::TempDir;
+
+    fn set_up(file_type: FileType, lines: &[&str]) -> Buffer {
+        Buffer {
+            lines: lines.iter().map(|line| Line::from(line)).collect(),
+            file_type,
+            ..Buffer::default()
+        }
+    }
+
+    fn to_strings(buffer: &Buffer) -> Vec<String> {
+        buffer.lines.iter().map(ToString::to_string).collect()
+    }
+
+    fn location(line_index: usize, grapheme_index: usize) -> Location {
+        Location {
+            grapheme_index,
+            line_index,
+        }
+    }
+
+    #[test]
+    fn test_binary_bytes() {
+        let mut buffer = Buffer::read(&b"GIF89a\0\x01"[..]).unwrap();
+        assert_eq!(buffer.bytes(), Some(&b"GIF89a\0\x01"[..]));
+        assert!(buffer.lines.is_empty());
+        assert!(buffer.set_byte(7, 0xff));
+        assert!(!buffer.set_byte(8, 0));
+        assert!(buffer.is_modified());
+        assert!(buffer.make_text().is_err());
+        assert!(Buffer::read(&b"text\x01"[..]).unwrap().is_binary());
+        assert!(!Buffer::read(&b"\x1b[1mbold\x1b[0m\tand\x0c\r\n"[..])
+            .unwrap()
+            .is_binary());
+        let mut buffer = set_up(FileType::Text, &["first", "second"]);
+        buffer.make_binary().unwrap();
+        assert_eq!(buffer.bytes(), Some(&b"first\nsecond\n"[..]));
+        assert!(buffer.set_byte(0, b'F'));
+        buffer.make_text().unwrap();
+        assert_eq!(to_strings(&buffer), vec!["First", "second"]);
+    }
+
+    #[test]
+    fn test_text_not_utf8() {
+        let mut buffer = Buffer::read(&b"caf\xe9\nna\xefve\n"[..]).unwrap();
+        assert!(!buffer.is_binary());
+        assert_eq!(to_strings(&buffer), vec!["caf\u{fffd}", "na\u{fffd}ve"]);
+        assert!(buffer.is_lossy());
+        assert!(buffer.is_read_only());
+        assert!(buffer.make_binary().is_err());
+        buffer.bytes = Some(b"caf\xe9".to_vec());
+        assert!(buffer.make_text().is_err());
+        assert_eq!(buffer.bytes(), Some(&b"caf\xe9"[..]));
+    }
+
+    #[test]
+    fn test_big_binary_file_refused() {
+        let directory = TempDir::new("big-binary");
+        let path = directory.join("big.bin");
+        let mut content = vec![0; usize::try_from(LAZY_LOAD_THRESHOLD).unwrap()];
+        content.extend_from_slice(b"tail");
+        std::fs::write(&path, content).unwrap();
+        let error = Buffer::load(path.to_str().unwrap()).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_big_file_read_when_shown() {
+        let directory = TempDir::new("big");
+        let path = directory.join("big.log");
+        let mut content =
+            b"line\n".repeat(usize::try_from(LAZY_LOAD_THRESHOLD.div_ceil(5)).unwrap());
+        content.extend_from_slice(b"na\xefve\n");
+        std::fs::write(&path, content).unwrap();
+        let mut buffer = Buffer::load(path.to_str().unwrap()).unwrap();
+        while buffer.is_loading() {
+            buffer.poll_loader();
+        }
+        assert_eq!(buffer.lines.last().unwrap().to_string(), "na\u{fffd}ve");
+        assert!(buffer.is_read_only());
+        buffer.read_only = false;
+        buffer.insert_char('!', &location(0, 4));
+        assert_eq!(buffer.save().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(buffer.lines.first().unwrap().to_string(), "line!");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_through_link_with_backup() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+        let directory = TempDir::new("backup");
+        let target = directory.join("target.sh");
+        fs::write(&target, "old\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o751)).unwrap();
+        let link = directory.join("link.sh");
+        symlink("target.sh", &link).unwrap();
+        let mut buffer = Buffer::load(link.to_str().unwrap()).unwrap();
+        buffer.set_keeps_backup(true);
+        buffer.insert_text(&location(0, 0), "new ");
+        buffer.save().unwrap();
+        let is_link = fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink();
+        let content = fs::read_to_string(&target).unwrap();
+        let backup = fs::read_to_string(directory.join("target.sh~")).unwrap();
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        let file_count = fs::read_dir(directory.path()).unwrap().count();
+        assert!(is_link);
+        assert_eq!(content, "new old\n");
+        assert_eq!(backup, "old\n");
+        assert_eq!(mode & 0o777, 0o751);
+        assert_eq!(file_count, 3);
+    }
+
+    #[test]
+    fn test_stream_and_save_as() {
+        let mut buffer = Buffer::stream(std::io::Cursor::new("first\nsecond\n"));
+        assert!(buffer.is_read_only());
+        assert!(buffer.save_as("unused.txt").is_err());
+        while buffer.is_loading() {
+            buffer.poll_loader();
+        }
+        assert_eq!(to_strings(&buffer), vec!["first", "second"]);
+        assert!(buffer.save().is_err());
+        let directory = TempDir::new("stream-save-as");
+        let path = directory.join("saved.rs");
+        let file_name = path.to_str().unwrap();
+        buffer.save_as(file_name).unwrap();
+        assert_eq!(buffer.file_name.as_deref(), Some(file_name));
+        assert_eq!(buffer.file_type, FileType::Rust);
+        buffer.insert_char('!', &location(1, 6));
+        buffer.save().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond!\n");
+    }
+
+    #[test]
+    fn test_save_keeps_line_endings() {
+        let directory = TempDir::new("line-endings");
+        let path = directory.join("windows.txt");
+        fs::write(&path, "first\r\nsecond").unwrap();
+        let mut buffer = Buffer::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(to_strings(&buffer), vec!["first", "second"]);
+        buffer.insert_line(&location(0, 5));
+        buffer.insert_char('!', &location(2, 6));
+        buffer.save().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\r\n\r\nsecond!");
+        buffer.make_binary().unwrap();
+        assert_eq!(buffer.bytes(), Some(&b"first\r\n\r\nsecond!"[..]));
+    }
+
+    #[test]
+    fn test_insert_line_keeps_indentation() {
+        let mut buffer = set_up(FileType::Text, &["    let a = 1;"]);
+        let caret = buffer.insert_line(&location(0, 8));
+        assert_eq!(to_strings(&buffer), vec!["    let ", "    a = 1;"]);
+        assert_eq!(caret, location(1, 4));
+    }
+
+    #[test]
+    fn test_insert_line_after_opening_brace() {
+        let mut buffer = set_up(FileType::Rust, &["    fn main() {"]);
+        let caret = buffer.insert_line(&location(0, 15));
+        assert_eq!(to_strings(&buffer), vec!["    fn main() {", "        "]);
+        assert_eq!(caret, location(1, 8));
+    }
+
+    #[test]
+    fn test_insert_line_between_brackets() {
+        let mut buffer = set_up(FileType::Rust, &["if a {}"]);
+        let caret = buffer.insert_line(&location(0, 6));
+        assert_eq!(to_strings(&buffer), vec!["if a {", "    ", "}"]);
+        assert_eq!(caret, location(1, 4));
+    }
+
+    #[test]
+    fn test_insert_line_after_colon() {
+        let mut buffer = set_up(FileType::Python, &["def main():"]);
+        let caret = buffer.insert_line(&location(0, 11));
+        assert_eq!(to_strings(&buffer), vec!["def main():", "    "]);
+        assert_eq!(caret, location(1, 4));
+        let mut buffer = set_up(FileType::Text, &["note:"]);
+        assert_eq!(buffer.insert_line(&location(0, 5)), location(1, 0));
+    }
+
+    #[test]
+    fn test_insert_line_strips_whitespace_only_line() {
+        let mut buffer = set_up(FileType::Rust, &["        "]);
+        let caret = buffer.insert_line(&location(0, 8));
+        assert_eq!(to_strings(&buffer), vec!["", "        "]);
+        assert_eq!(caret, location(1, 8));
+    }
+
+    #[test]
+    fn test_read_only_refuses_edits() {
+        let mut buffer = set_up(FileType::Text, &["text"]);
+        buffer.toggle_read_only();
+        buffer.insert_char('x', &location(0, 0));
+        buffer.delete(&location(0, 0));
+        assert_eq!(buffer.insert_line(&location(0, 2)), location(0, 2));
+        buffer.delete_range(&location(0, 0), &location(0, 2));
+        assert_eq!(buffer.open_line_above(0), location(0, 0));
+        assert_eq!(buffer.insert_text(&location(0, 1), "a\nb"), location(0, 1));
+        assert_eq!(to_strings(&buffer), vec!["text"]);
+        assert!(!buffer.is_modified());
+        buffer.toggle_read_only();
+        buffer.insert_char('x', &location(0, 0));
+        assert_eq!(to_strings(&buffer), vec!["xtext"]);
+        let mut buffer = set_up(FileType::Rust, &["    "]);
+        buffer.toggle_read_only();
+        assert_eq!(buffer.outdent_before('}', &location(0, 4)), 0);
+        assert_eq!(to_strings(&buffer), vec!["    "]);
+        assert!(!buffer.is_modified());
+    }
+
+    #[test]
+    fn test_edits_changing_nothing() {
+        let mut buffer = Buffer::default();
+        assert_eq!(buffer.insert_line(&location(0, 0)), location(0, 0));
+        buffer.delete(&location(0, 0));
+        assert!(!buffer.is_modified());
+        let mut filtered = set_up(FileType::Text, &["one", "two", "three"]).filtered(&[0, 2]);
+        filtered.delete(&location(0, 3));
+        assert_eq!(to_strings(&filtered), vec!["one", "three"]);
+        assert!(!filtered.is_modified());
+        filtered.delete(&location(0, 2));
+        assert!(filtered.is_modified());
+    }
+
+    #[test]
+    fn test_outdent_before_closing_brace() {
+        let mut buffer = set_up(FileType::Rust, &["        "]);
+        assert_eq!(buffer.outdent_before('}', &location(0, 8)), 4);
+        assert_eq!(to_strings(&buffer), vec!["    "]);
+        assert_eq!(buffer.outdent_before('a', &location(0, 4)), 0);
+        let mut buffer = set_up(FileType::Rust, &["  x"]);
+        assert_eq!(buffer.outdent_before('}', &location(0, 3)), 0);
+    }
+
+    #[test]
+    fn test_insert_text() {
+        let mut buffer = set_up(FileType::Text, &["let a = b;"]);
+        assert_eq!(buffer.insert_text(&location(0, 4), "x"), location(0, 5));
+        assert_eq!(
+            buffer.insert_text(&location(0, 9), " c\nd\ne"),
+            location(2, 1)
+        );
+        assert_eq!(to_strings(&buffer), vec!["let xa =  c", "d", "eb;"]);
+        assert_eq!(
+            buffer.text_in_range(&location(0, 9), &location(2, 1)),
+            " c\nd\ne"
+        );
+    }
+
+    #[test]
+    fn test_open_line_above() {
+        let mut buffer = set_up(FileType::Text, &["a", "    b"]);
+        assert_eq!(buffer.open_line_above(1), location(1, 4));
+        assert_eq!(to_strings(&buffer), vec!["a", "    ", "    b"]);
+    }
+
+    #[test]
+    fn test_word_movement_across_lines() {
+        let buffer = set_up(FileType::Text, &["let a = b;", "    c"]);
+        assert_eq!(buffer.word_end_after(&location(0, 9)), location(0, 10));
+        assert_eq!(buffer.word_end_after(&location(0, 10)), location(1, 0));
+        assert_eq!(buffer.word_end_after(&location(1, 0)), location(1, 5));
+        assert_eq!(buffer.word_start_before(&location(1, 4)), location(1, 0));
+        assert_eq!(buffer.word_start_before(&location(1, 0)), location(0, 10));
+        assert_eq!(buffer.word_start_before(&location(0, 0)), location(0, 0));
+        assert_eq!(buffer.word_start_after(&location(0, 8)), location(0, 9));
+        assert_eq!(buffer.word_start_after(&location(0, 9)), location(1, 4));
+        assert_eq!(buffer.word_start_after(&location(1, 4)), location(1, 5));
+    }
+
+    #[test]
+    fn test_matching_bracket_across_lines() {
+        let mut buffer = set_up(FileType::Rust, &["fn a() {", "    b[0] = (1);", "}"]);
+        assert_eq!(
+            buffer.matching_bracket(&location(0, 7), MAX_BRACKET_SEARCH_LINES),
+            Some(location(2, 0))
+        );
+        assert_eq!(
+            buffer.matching_bracket(&location(2, 0), MAX_BRACKET_SEARCH_LINES),
+            Some(location(0, 7))
+        );
+        assert_eq!(
+            buffer.matching_bracket(&location(1, 5), MAX_BRACKET_SEARCH_LINES),
+            Some(location(1, 7))
+        );
+        assert_eq!(
+            buffer.matching_bracket(&location(1, 4), MAX_BRACKET_SEARCH_LINES),
+            None
+        );
+    }
+
+    #[test]
+    fn test_matching_bracket_skips_strings_and_comments() {
+        let mut buffer = set_up(FileType::Rust, &["f(\")\", ')', // )", "/* ( */ 1)"]);
+        assert_eq!(
+            buffer.matching_bracket(&location(0, 1), MAX_BRACKET_SEARCH_LINES),
+            Some(location(1, 9))
+        );
+        let mut buffer = set_up(FileType::Text, &["f(\")\""]);
+        assert_eq!(
+            buffer.matching_bracket(&location(0, 1), MAX_BRACKET_SEARCH_LINES),
+            Some(location(0, 3))
+        );
+    }
+
+    #[test]
+    fn test_find_next() {
+        let buffer = set_up(FileType::Text, &["a ab", "ab 𝒻b", "b"]);
+        assert_eq!(
+            buffer.find_next("ab", &location(0, 0)),
+            Some(location(0, 2))
+        );
+        assert_eq!(
+            buffer.find_next("ab", &location(0, 3)),
+            Some(location(1, 0))
+        );
+        assert_eq!(
+            buffer.find_next("𝒻b", &location(2, 0)),
+            Some(location(1, 3))
+        );
+        assert_eq!(
+            buffer.find_next("a ", &location(1, 0)),
+            Some(location(0, 0))
+        );
+        assert_eq!(buffer.find_next("ba", &location(0, 0)), None);
+        assert_eq!(buffer.find_next("", &location(0, 0)), None);
+    }
+
+    #[test]
+    fn test_bracket_pair() {
+        let mut buffer = set_up(FileType::Rust, &["a(b[c], d)"]);
+        let pair = |buffer: &mut Buffer, grapheme_index| {
+            buffer
+                .bracket_pair(&location(0, grapheme_index))
+                .map(|(first, second)| (first.grapheme_index, second.grapheme_index))
+        };
+        assert_eq!(pair(&mut buffer, 3), Some((3, 5)));
+        assert_eq!(pair(&mut buffer, 6), Some((5, 3)));
+        assert_eq!(pair(&mut buffer, 8), Some((1, 9)));
+        assert_eq!(pair(&mut buffer, 0), None);
+        let mut lines = vec!["{"; 1];
+        lines.extend(["x"; MAX_HIGHLIGHT_SEARCH_LINES]);
+        lines.push("}");
+        let mut buffer = set_up(FileType::Rust, &lines);
+        let caret = location(MAX_HIGHLIGHT_SEARCH_LINES, 1);
+        assert_eq!(buffer.highlighted_bracket_pair(&caret), None);
+        assert_eq!(
+            buffer.bracket_pair(&caret),
+            Some((
+                location(0, 0),
+                location(MAX_HIGHLIGHT_SEARCH_LINES.saturating_add(1), 0)
+            ))
+        );
     }
 }
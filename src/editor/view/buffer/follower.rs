@@ -0,0 +1,163 @@
+use crate::editor::view::buffer::loader::trim_line_ending;
+use crate::editor::view::line::Line;
+use std::fs::{self, File, Metadata};
+use std::io::{Error, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+/// What happened to the followed file since the last poll
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Growth {
+    /// Text was written at its end
+    Appended,
+    /// It became shorter than what was read, it is read again from the start
+    Truncated,
+    /// Another file took its name, like when logs are rotated, the new one is read from the start
+    Replaced,
+}
+
+/// The lines read from the followed file by [`Follower::poll`]
+pub struct Update {
+    pub growth: Growth,
+    pub lines: Vec<Line>,
+    /// The first line continues the last line read before, which did not end yet: it replaces it
+    pub continues_last: bool,
+}
+
+/// This reads what is written at the end of a file as it grows, like `tail -F`.
+/// A line that does not end yet is given as it is, then given again in full once it grows.
+pub struct Follower {
+    path: PathBuf,
+    file: File,
+    /// Tells whether another file took the name of the followed one
+    identity: (u64, u64),
+    /// Where the next read starts
+    offset: u64,
+    /// The bytes of the last line read, if it did not end yet
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    /// Follows `file_name` from `offset`, where the text already read ends.
+    /// `last_line` is the last line of that text, continued by the next bytes if the file does not end with a newline.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can not be opened or read
+    pub fn new(file_name: &str, offset: u64, last_line: &str) -> Result<Self, Error> {
+        let mut file = File::open(file_name)?;
+        let identity = identity(&file.metadata()?);
+        let mut partial = Vec::new();
+        if offset > 0 {
+            let mut last_byte = [0];
+            file.seek(SeekFrom::Start(offset.saturating_sub(1)))?;
+            file.read_exact(&mut last_byte)?;
+            if last_byte != *b"\n" {
+                partial = last_line.as_bytes().to_vec();
+            }
+        }
+        Ok(Self {
+            path: PathBuf::from(file_name),
+            file,
+            identity,
+            offset,
+            partial,
+        })
+    }
+
+    /// Returns the lines written since the last call, or all the lines of the file if it was truncated or replaced.
+    /// Returns `None` if the file did not change or does not exist, between the rotation of logs and the creation
+    /// of the new file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can not be opened or read
+    pub fn poll(&mut self) -> Result<Option<Update>, Error> {
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Ok(None);
+        };
+        let growth = if identity(&metadata) != self.identity {
+            self.file = File::open(&self.path)?;
+            self.identity = identity(&self.file.metadata()?);
+            Growth::Replaced
+        } else if metadata.len() < self.offset {
+            Growth::Truncated
+        } else if metadata.len() > self.offset {
+            Growth::Appended
+        } else {
+            return Ok(None);
+        };
+        if growth != Growth::Appended {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        let continues_last = !self.partial.is_empty();
+        let mut bytes = std::mem::take(&mut self.partial);
+        self.file.seek(SeekFrom::Start(self.offset))?;
+        let count = self.file.read_to_end(&mut bytes)?;
+        self.offset = self
+            .offset
+            .saturating_add(u64::try_from(count).unwrap_or(u64::MAX));
+        let raw_lines: Vec<&[u8]> = bytes.split_inclusive(|byte| *byte == b'\n').collect();
+        if let Some(last) = raw_lines.last().filter(|last| !last.ends_with(b"\n")) {
+            self.partial = last.to_vec();
+        }
+        let lines = raw_lines
+            .into_iter()
+            .map(|raw_line| Line::from(trim_line_ending(&String::from_utf8_lossy(raw_line))))
+            .collect();
+        Ok(Some(Update {
+            growth,
+            lines,
+            continues_last,
+        }))
+    }
+}
+
+/// The device and inode of the file, which change when another file takes its name
+#[cfg(unix)]
+fn identity(metadata: &Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+/// Only truncation can be noticed without inodes
+#[cfg(not(unix))]
+fn identity(_metadata: &Metadata) -> (u64, u64) {
+    (0, 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::test::TempDir;
+
+    fn to_strings(update: &Update) -> Vec<String> {
+        update.lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_follow_growth_truncation_and_rotation() {
+        let directory = TempDir::new("follower");
+        let path = directory.join("service.log");
+        fs::write(&path, "one\ntw").unwrap();
+        let mut follower = Follower::new(path.to_str().unwrap(), 6, "tw").unwrap();
+        assert!(follower.poll().unwrap().is_none());
+        fs::write(&path, "one\ntwo\nthr").unwrap();
+        let update = follower.poll().unwrap().unwrap();
+        assert_eq!(update.growth, Growth::Appended);
+        assert!(update.continues_last);
+        assert_eq!(to_strings(&update), vec!["two", "thr"]);
+        fs::write(&path, "new\n").unwrap();
+        let update = follower.poll().unwrap().unwrap();
+        assert_eq!(update.growth, Growth::Truncated);
+        assert!(!update.continues_last);
+        assert_eq!(to_strings(&update), vec!["new"]);
+        fs::rename(&path, directory.join("service.log.1")).unwrap();
+        assert!(follower.poll().unwrap().is_none());
+        fs::write(&path, "rotated\r\n").unwrap();
+        let update = follower.poll().unwrap().unwrap();
+        #[cfg(unix)]
+        assert_eq!(update.growth, Growth::Replaced);
+        assert_eq!(to_strings(&update), vec!["rotated"]);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::editor::filetype::FileType;
+use crate::editor::view::buffer::lines::Lines;
+
+/// What the scanner is inside of at some point of the text
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+enum State {
+    #[default]
+    Code,
+    /// Rust block comments can be nested
+    BlockComment(usize),
+    String(char),
+    /// A Rust raw string, with its number of `#`
+    RawString(usize),
+    /// A Python string delimited by three quotes
+    TripleQuoted(char),
+}
+
+/// This finds which graphemes are inside string literals and comments, for the languages that have rules for them.
+/// The state at the start of each line is cached, so only the lines after an edit have to be scanned again.
+#[derive(Default)]
+pub struct Highlighter {
+    line_states: Vec<State>,
+}
+
+impl Highlighter {
+    /// Forgets what was scanned after `line_index`, to be called when this line changes
+    pub fn invalidate_from(&mut self, line_index: usize) {
+        self.line_states.truncate(line_index.saturating_add(1));
+    }
+
+    /// Returns, for every grapheme of the line `line_index`, `true` if it is part of a string literal or a comment
+    pub fn literal_graphemes(
+        &mut self,
+        file_type: FileType,
+        lines: &Lines,
+        line_index: usize,
+    ) -> Vec<bool> {
+        let Some(line) = lines.get(line_index) else {
+            return Vec::new();
+        };
+        if file_type == FileType::Text {
+            return vec![false; line.grapheme_count()];
+        }
+        if self.line_states.is_empty() {
+            self.line_states.push(State::Code);
+        }
+        while self.line_states.len() <= line_index {
+            let scanned_index = self.line_states.len().saturating_sub(1);
+            let state = self.line_states.last().copied().unwrap_or_default();
+            let next_state = lines.get_uncached(scanned_index).map_or(state, |scanned| {
+                scan_line(file_type, state, &scanned.graphemes().collect::<Vec<_>>()).1
+            });
+            self.line_states.push(next_state);
+        }
+        let state = self
+            .line_states
+            .get(line_index)
+            .copied()
+            .unwrap_or_default();
+        scan_line(file_type, state, &line.graphemes().collect::<Vec<_>>()).0
+    }
+}
+
+fn is_identifier(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .all(|character| character.is_alphanumeric() || character == '_')
+}
+
+/// Looks for the start of a literal at `index`, in code.
+/// Returns how many graphemes of literal were found, 0 if there are none, and the state after them.
+fn scan_code(file_type: FileType, graphemes: &[&str], index: usize) -> (usize, State) {
+    let at = |index: usize| graphemes.get(index).copied().unwrap_or_default();
+    let current = at(index);
+    let next = at(index.saturating_add(1));
+    let mut step: usize = 1;
+    let mut new_state = State::Code;
+    match (file_type, current) {
+        (FileType::Rust, "/") if next == "/" => step = graphemes.len(),
+        (FileType::Python, "#") => step = graphemes.len(),
+        (FileType::Rust, "/") if next == "*" => {
+            new_state = State::BlockComment(1);
+            step = 2;
+        }
+        (FileType::Python, "\"" | "'")
+            if next == current && at(index.saturating_add(2)) == current =>
+        {
+            new_state = State::TripleQuoted(current.chars().next().unwrap_or('"'));
+            step = 3;
+        }
+        (FileType::Rust | FileType::Python, "\"") | (FileType::Python, "'") => {
+            new_state = State::String(current.chars().next().unwrap_or('"'));
+        }
+        (FileType::Rust, "r")
+            if index == 0
+                || !is_identifier(at(index.saturating_sub(1)))
+                || at(index.saturating_sub(1)) == "b" =>
+        {
+            let hashes = graphemes
+                .iter()
+                .skip(index.saturating_add(1))
+                .take_while(|grapheme| **grapheme == "#")
+                .count();
+            if at(index.saturating_add(1).saturating_add(hashes)) == "\"" {
+                new_state = State::RawString(hashes);
+                step = hashes.saturating_add(2);
+            } else {
+                step = 0;
+            }
+        }
+        (FileType::Rust, "'") => {
+            // A character literal, like 'a' or '\n', and not a lifetime like 'a
+            let closing = if next == "\\" {
+                graphemes
+                    .iter()
+                    .skip(index.saturating_add(3))
+                    .take(8)
+                    .position(|grapheme| *grapheme == "'")
+                    .map(|position| position.saturating_add(4))
+            } else if at(index.saturating_add(2)) == "'" {
+                Some(3)
+            } else {
+                None
+            };
+            step = closing.unwrap_or(0);
+        }
+        _ => step = 0,
+    }
+    (step, new_state)
+}
+
+/// Scans the graphemes of a line starting in `state`.
+/// Returns which graphemes are literals and the state at the start of the next line.
+fn scan_line(file_type: FileType, mut state: State, graphemes: &[&str]) -> (Vec<bool>, State) {
+    let at = |index: usize| graphemes.get(index).copied().unwrap_or_default();
+    let mut literal = vec![false; graphemes.len()];
+    let mut mark = |from: usize, count: usize| {
+        for is_literal in literal.iter_mut().skip(from).take(count) {
+            *is_literal = true;
+        }
+    };
+    let mut index: usize = 0;
+    while index < graphemes.len() {
+        let current = at(index);
+        let next = at(index.saturating_add(1));
+        let mut step: usize = 1;
+        match state {
+            State::Code => (step, state) = scan_code(file_type, graphemes, index),
+            State::String(quote) => {
+                if current == "\\" {
+                    step = 2;
+                } else if current.starts_with(quote) {
+                    state = State::Code;
+                }
+            }
+            State::TripleQuoted(quote) => {
+                if current == "\\" {
+                    step = 2;
+                } else if current.starts_with(quote)
+                    && next.starts_with(quote)
+                    && at(index.saturating_add(2)).starts_with(quote)
+                {
+                    state = State::Code;
+                    step = 3;
+                }
+            }
+            State::BlockComment(depth) => {
+                if current == "*" && next == "/" {
+                    state = match depth.saturating_sub(1) {
+                        0 => State::Code,
+                        depth => State::BlockComment(depth),
+                    };
+                    step = 2;
+                } else if current == "/" && next == "*" {
+                    state = State::BlockComment(depth.saturating_add(1));
+                    step = 2;
+                }
+            }
+            State::RawString(hashes) => {
+                let closes = current == "\""
+                    && graphemes
+                        .iter()
+                        .skip(index.saturating_add(1))
+                        .take(hashes)
+                        .filter(|grapheme| **grapheme == "#")
+                        .count()
+                        == hashes;
+                if closes {
+                    state = State::Code;
+                    step = hashes.saturating_add(1);
+                }
+            }
+        }
+        // A step of 0 is a grapheme of code
+        mark(index, step);
+        index = index.saturating_add(step.max(1));
+    }
+    if file_type == FileType::Python && matches!(state, State::String(_)) {
+        // Python strings with a single quote end with the line
+        state = State::Code;
+    }
+    (literal, state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::view::line::Line;
+
+    fn literals(file_type: FileType, text: &[&str]) -> Vec<String> {
+        let lines: Lines = text.iter().map(|line| Line::from(line)).collect();
+        let mut highlighter = Highlighter::default();
+        (0..lines.len())
+            .map(|line_index| {
+                highlighter
+                    .literal_graphemes(file_type, &lines, line_index)
+                    .iter()
+                    .map(|is_literal| if *is_literal { 'L' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_strings_and_comments() {
+        assert_eq!(
+            literals(FileType::Rust, &["f(\"(\\\"\") // )", "'a' '\\n' &'a x"]),
+            vec!["..LLLLL..LLLL", "LLL.LLLL......"]
+        );
+    }
+
+    #[test]
+    fn test_rust_multiline_literals() {
+        assert_eq!(
+            literals(
+                FileType::Rust,
+                &["a /* /*", "( */ b */ (", "r#\"\"", "\"# {"]
+            ),
+            vec!["..LLLLL", "LLLLLLLLL..", "LLLL", "LL.."]
+        );
+    }
+
+    #[test]
+    fn test_python_strings_and_comments() {
+        assert_eq!(
+            literals(FileType::Python, &["x = '(' # )", "\"\"\"(", ")\"\"\" ("]),
+            vec!["....LLL.LLL", "LLLL", "LLLL.."]
+        );
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut lines: Lines = [Line::from("/*"), Line::from("(")].into_iter().collect();
+        let mut highlighter = Highlighter::default();
+        assert_eq!(
+            highlighter.literal_graphemes(FileType::Rust, &lines, 1),
+            vec![true]
+        );
+        *lines.first_mut().unwrap() = Line::from("//");
+        highlighter.invalidate_from(0);
+        assert_eq!(
+            highlighter.literal_graphemes(FileType::Rust, &lines, 1),
+            vec![false]
+        );
+    }
+}
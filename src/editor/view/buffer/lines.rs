@@ -0,0 +1,249 @@
+use crate::editor::view::buffer::loader::trim_line_ending;
+use crate::editor::view::line::Line;
+use std::borrow::Cow;
+use std::cell::{Cell, OnceCell};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::ops::{Range, RangeBounds};
+
+/// How many lines read from the file are kept before the ones out of sight are dropped, see [`Lines::release`]
+const MAX_CACHED_LINES: usize = 65536;
+
+/// A line of [`Lines`]
+pub struct Slot {
+    /// Where the line starts in the file, as long as it is the line of the file
+    start: Option<u64>,
+    /// The line, built the first time it is needed for the lines of the file
+    line: OnceCell<Line>,
+}
+
+impl Slot {
+    /// The line of the file starting at `start`, read when it is needed
+    pub fn on_disk(start: u64) -> Self {
+        Self {
+            start: Some(start),
+            line: OnceCell::new(),
+        }
+    }
+
+    pub fn loaded(line: Line) -> Self {
+        Self {
+            start: None,
+            line: OnceCell::from(line),
+        }
+    }
+}
+
+/// The lines of a buffer. The lines of a big file are only known by where they start in it:
+/// they are read when they are shown or searched, and dropped again once many were read,
+/// so a file of several gigabytes takes a fraction of its size in memory. Edited lines are kept.
+#[derive(Default)]
+pub struct Lines {
+    slots: Vec<Slot>,
+    /// The file the lines that were not edited are read from
+    source: Option<File>,
+    /// How many lines were read from the file since the last [`Lines::release`]
+    cached: Cell<usize>,
+    /// Some lines were not UTF-8, their invalid bytes were replaced
+    is_lossy: bool,
+}
+
+impl Lines {
+    /// Lines read from `source` when they are needed, see [`Slot::on_disk`]
+    pub fn from_file(source: File) -> Self {
+        Self {
+            source: Some(source),
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns the line `index`, reading it from the file if it was not yet
+    pub fn get(&self, index: usize) -> Option<&Line> {
+        self.slots.get(index).map(|slot| self.line_of(slot))
+    }
+
+    /// Returns the line `index` without keeping it if it has to be read from the file,
+    /// for the searches going through many lines
+    pub fn get_uncached(&self, index: usize) -> Option<Cow<'_, Line>> {
+        let slot = self.slots.get(index)?;
+        Some(slot.line.get().map_or_else(
+            || Cow::Owned(read_line(self.source.as_ref(), slot.start)),
+            Cow::Borrowed,
+        ))
+    }
+
+    /// Returns the line `index` to edit it, it is not read from the file anymore
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Line> {
+        let Self { slots, source, .. } = self;
+        let slot = slots.get_mut(index)?;
+        if slot.line.get().is_none() {
+            let _ = slot.line.set(read_line(source.as_ref(), slot.start));
+        }
+        slot.start = None;
+        slot.line.get_mut()
+    }
+
+    pub fn last(&self) -> Option<&Line> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Line> {
+        self.slots.iter().map(|slot| self.line_of(slot))
+    }
+
+    /// Goes through the lines like [`Lines::get_uncached`]
+    pub fn iter_uncached(&self) -> impl Iterator<Item = Cow<'_, Line>> {
+        (0..self.len()).filter_map(|index| self.get_uncached(index))
+    }
+
+    pub fn push(&mut self, line: Line) {
+        self.slots.push(Slot::loaded(line));
+    }
+
+    pub fn pop(&mut self) {
+        self.slots.pop();
+    }
+
+    pub fn insert(&mut self, index: usize, line: Line) {
+        self.slots.insert(index, Slot::loaded(line));
+    }
+
+    /// Inserts `lines` before the line `index`
+    pub fn insert_lines(&mut self, index: usize, lines: impl IntoIterator<Item = Line>) {
+        let index = index.min(self.len());
+        self.slots
+            .splice(index..index, lines.into_iter().map(Slot::loaded))
+            .for_each(drop);
+    }
+
+    /// Removes the line `index` and returns it
+    pub fn remove(&mut self, index: usize) -> Line {
+        let slot = self.slots.remove(index);
+        slot.line
+            .into_inner()
+            .unwrap_or_else(|| read_line(self.source.as_ref(), slot.start))
+    }
+
+    pub fn remove_range(&mut self, range: impl RangeBounds<usize>) {
+        self.slots.drain(range);
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.is_lossy = false;
+    }
+
+    pub fn extend(&mut self, lines: impl IntoIterator<Item = Line>) {
+        self.slots.extend(lines.into_iter().map(Slot::loaded));
+    }
+
+    /// Adds the lines found by a loader, see [`crate::editor::view::buffer::loader::Loader`]
+    pub fn append_slots(&mut self, slots: &mut Vec<Slot>) {
+        self.slots.append(slots);
+    }
+
+    /// The lines are read from a file when needed, see [`Lines::from_file`]
+    pub fn is_indexed(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Some lines were not UTF-8: saving them would write replacement characters instead of their bytes
+    pub fn is_lossy(&self) -> bool {
+        self.is_lossy
+    }
+
+    pub fn mark_lossy(&mut self) {
+        self.is_lossy = true;
+    }
+
+    /// Drops the lines read from the file outside of `kept` once too many were read, they are read again when needed
+    pub fn release(&mut self, kept: &Range<usize>) {
+        if self.cached.get() <= MAX_CACHED_LINES {
+            return;
+        }
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.start.is_some() && !kept.contains(&index) {
+                slot.line.take();
+            }
+        }
+        self.cached.set(0);
+    }
+
+    fn line_of<'a>(&'a self, slot: &'a Slot) -> &'a Line {
+        slot.line.get_or_init(|| {
+            self.cached.set(self.cached.get().saturating_add(1));
+            read_line(self.source.as_ref(), slot.start)
+        })
+    }
+}
+
+/// The accessors of slices the tests use
+#[cfg(test)]
+impl Lines {
+    pub fn first(&self) -> Option<&Line> {
+        self.get(0)
+    }
+
+    pub fn first_mut(&mut self) -> Option<&mut Line> {
+        self.get_mut(0)
+    }
+}
+
+impl FromIterator<Line> for Lines {
+    fn from_iter<T: IntoIterator<Item = Line>>(lines: T) -> Self {
+        Self {
+            slots: lines.into_iter().map(Slot::loaded).collect(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Reads the line starting at `start` in `source`.
+/// A line that can not be read anymore, because the file changed on disk, is empty.
+fn read_line(source: Option<&File>, start: Option<u64>) -> Line {
+    let Some((mut file, start)) = source.zip(start) else {
+        return Line::default();
+    };
+    let mut raw_line = Vec::new();
+    let _ = file
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| BufReader::new(file).read_until(b'\n', &mut raw_line));
+    Line::from(trim_line_ending(&String::from_utf8_lossy(&raw_line)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::test::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_read_lines_when_needed() {
+        let directory = TempDir::new("lines");
+        let path = directory.join("lines.txt");
+        fs::write(&path, "first\nsecond\r\nthird").unwrap();
+        let mut lines = Lines::from_file(File::open(&path).unwrap());
+        lines.append_slots(&mut vec![
+            Slot::on_disk(0),
+            Slot::on_disk(6),
+            Slot::on_disk(14),
+        ]);
+        assert_eq!(lines.get_uncached(1).unwrap().to_string(), "second");
+        assert!(lines.slots.get(1).unwrap().line.get().is_none());
+        assert_eq!(lines.last().unwrap().to_string(), "third");
+        lines.get_mut(0).unwrap().insert_character('!', 5);
+        lines.cached.set(MAX_CACHED_LINES.saturating_add(1));
+        lines.release(&(1..2));
+        assert!(lines.slots.get(2).unwrap().line.get().is_none());
+        let texts: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        assert_eq!(texts, vec!["first!", "second", "third"]);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::editor::view::buffer::lines::{Lines, Slot};
+use crate::editor::view::line::Line;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+/// The first batch is kept small so the first screen can be shown as soon as possible
+const FIRST_BATCH_SIZE: usize = 256;
+const BATCH_SIZE: usize = 8192;
+
+struct Batch {
+    slots: Vec<Slot>,
+    bytes_read: u64,
+    /// Some of the lines are not UTF-8
+    is_lossy: bool,
+    endings: Endings,
+}
+
+/// How the lines of a text end, so saving writes them back as they were read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Endings {
+    /// The lines end with `\r\n`, as the first one does, instead of `\n`
+    pub crlf: bool,
+    /// The last line ends with a line break too
+    pub final_newline: bool,
+}
+
+impl Default for Endings {
+    fn default() -> Self {
+        Self {
+            crlf: false,
+            final_newline: true,
+        }
+    }
+}
+
+impl Endings {
+    /// Finds how the lines of `text` end
+    pub fn of(text: &[u8]) -> Self {
+        let mut endings = Self::default();
+        if let Some(first_line) = text.split_inclusive(|byte| *byte == b'\n').next() {
+            endings.read_first(first_line);
+        }
+        if let Some(last_line) = text.split_inclusive(|byte| *byte == b'\n').next_back() {
+            endings.read_last(last_line);
+        }
+        endings
+    }
+
+    /// The first line, with its line break, tells which line break the text uses
+    fn read_first(&mut self, raw_line: &[u8]) {
+        self.crlf = raw_line.ends_with(b"\r\n");
+    }
+
+    /// The last line, with its line break, tells if the text ends with one
+    fn read_last(&mut self, raw_line: &[u8]) {
+        self.final_newline = raw_line.ends_with(b"\n");
+    }
+
+    pub fn line_break(self) -> &'static str {
+        if self.crlf {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+}
+
+/// This represents a file being read in the background.
+/// Lines are split on a separate thread and sent in batches,
+/// so the editor can display and scroll through the beginning of the file while the rest is still being read.
+pub struct Loader {
+    receiver: Receiver<Batch>,
+    total_bytes: u64,
+    bytes_read: u64,
+    endings: Endings,
+    finished: bool,
+}
+
+impl Loader {
+    /// Starts reading `source`, which can not be read again, on a new thread: its lines are turned into [`Line`].
+    /// `total_bytes` is only used to compute the progress, it can be 0 if the size is unknown.
+    pub fn spawn<R: Read + Send + 'static>(source: R, total_bytes: u64) -> Self {
+        Self::start(source, total_bytes, |raw_line, _| {
+            Slot::loaded(Line::from(trim_line_ending(&String::from_utf8_lossy(
+                raw_line,
+            ))))
+        })
+    }
+
+    /// Starts indexing `file` on a new thread: only where its lines start is sent, they are read when needed,
+    /// see [`Lines::from_file`]
+    pub fn index(file: File, total_bytes: u64) -> Self {
+        Self::start(file, total_bytes, |_, start| Slot::on_disk(start))
+    }
+
+    /// Reads `source` on a new thread, sending what `to_slot` makes of each line and where it starts
+    fn start<R, F>(source: R, total_bytes: u64, to_slot: F) -> Self
+    where
+        R: Read + Send + 'static,
+        F: Fn(&[u8], u64) -> Slot + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(source);
+            let mut raw_line = Vec::new();
+            let mut batch = Vec::with_capacity(FIRST_BATCH_SIZE);
+            let mut batch_size = FIRST_BATCH_SIZE;
+            let mut bytes_read: u64 = 0;
+            let mut is_lossy = false;
+            let mut endings = Endings::default();
+            loop {
+                raw_line.clear();
+                // An error while reading ends the load, what has been read so far is kept
+                let count = reader.read_until(b'\n', &mut raw_line).unwrap_or(0);
+                if count == 0 {
+                    break;
+                }
+                if bytes_read == 0 {
+                    endings.read_first(&raw_line);
+                }
+                endings.read_last(&raw_line);
+                batch.push(to_slot(&raw_line, bytes_read));
+                is_lossy = is_lossy || std::str::from_utf8(&raw_line).is_err();
+                bytes_read = bytes_read.saturating_add(u64::try_from(count).unwrap_or(u64::MAX));
+                if batch.len() >= batch_size {
+                    let slots = std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE));
+                    let batch = Batch {
+                        slots,
+                        bytes_read,
+                        is_lossy: std::mem::take(&mut is_lossy),
+                        endings,
+                    };
+                    if sender.send(batch).is_err() {
+                        // The buffer has been dropped, nobody is waiting for the rest of the file
+                        return;
+                    }
+                    batch_size = BATCH_SIZE;
+                }
+            }
+            let _ = sender.send(Batch {
+                slots: batch,
+                bytes_read,
+                is_lossy,
+                endings,
+            });
+        });
+        Self {
+            receiver,
+            total_bytes,
+            bytes_read: 0,
+            endings: Endings::default(),
+            finished: false,
+        }
+    }
+
+    /// Appends every line received since the last call to `lines`, without blocking.
+    /// Returns `true` if there were some.
+    pub fn receive_lines(&mut self, lines: &mut Lines) -> bool {
+        let mut received = false;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(mut batch) => {
+                    received = received || !batch.slots.is_empty();
+                    lines.append_slots(&mut batch.slots);
+                    if batch.is_lossy {
+                        lines.mark_lossy();
+                    }
+                    self.bytes_read = batch.bytes_read;
+                    self.endings = batch.endings;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+        received
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// How much of the source the received lines take
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// How the lines received so far end
+    pub fn endings(&self) -> Endings {
+        self.endings
+    }
+
+    /// Returns the percentage of the source that has been read, or `None` if the size is unknown
+    pub fn progress(&self) -> Option<u8> {
+        let percent = self
+            .bytes_read
+            .saturating_mul(100)
+            .checked_div(self.total_bytes)?;
+        Some(u8::try_from(percent.min(100)).unwrap_or(100))
+    }
+}
+
+/// Removes the `\n` or `\r\n` ending `line`
+pub fn trim_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::test::TempDir;
+    use std::io::Cursor;
+
+    fn load_all(content: &'static str) -> Vec<String> {
+        let mut loader = Loader::spawn(Cursor::new(content), content.len().try_into().unwrap());
+        let mut lines = Lines::default();
+        while !loader.is_finished() {
+            loader.receive_lines(&mut lines);
+        }
+        assert_eq!(loader.progress(), Some(100));
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_load_lines() {
+        assert_eq!(
+            load_all("first\r\nsecond\nthird"),
+            vec!["first", "second", "third"]
+        );
+        assert_eq!(load_all("first\n\nthird\n"), vec!["first", "", "third"]);
+    }
+
+    #[test]
+    fn test_endings() {
+        let endings = |crlf, final_newline| Endings {
+            crlf,
+            final_newline,
+        };
+        assert_eq!(Endings::of(b"first\r\nsecond"), endings(true, false));
+        assert_eq!(Endings::of(b"first\nsecond\r\n"), endings(false, true));
+        assert_eq!(Endings::of(b""), Endings::default());
+        let mut loader = Loader::spawn(Cursor::new("first\r\nsecond"), 0);
+        while !loader.is_finished() {
+            loader.receive_lines(&mut Lines::default());
+        }
+        assert_eq!(loader.endings(), endings(true, false));
+    }
+
+    #[test]
+    fn test_load_in_batches() {
+        let content: &'static str = "line\n".repeat(FIRST_BATCH_SIZE.saturating_mul(3)).leak();
+        assert_eq!(load_all(content).len(), FIRST_BATCH_SIZE.saturating_mul(3));
+    }
+
+    #[test]
+    fn test_index_file() {
+        let directory = TempDir::new("index");
+        let path = directory.join("index.txt");
+        std::fs::write(&path, b"caf\xc3\xa9\nna\xefve\n").unwrap();
+        let mut loader = Loader::index(File::open(&path).unwrap(), 0);
+        let mut lines = Lines::from_file(File::open(&path).unwrap());
+        while !loader.is_finished() {
+            loader.receive_lines(&mut lines);
+        }
+        assert_eq!(loader.bytes_read(), 12);
+        assert!(lines.is_lossy());
+        let texts: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        assert_eq!(texts, vec!["café", "na\u{fffd}ve"]);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::editor::view::buffer::Buffer;
+use regex::Regex;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+enum Matcher {
+    Text(String),
+    Regex(Regex),
+}
+
+/// Which lines a filter shows: the ones containing a text, or matching a regular expression written
+/// between slashes, like `/^ERROR/`. With a `!` before it, the lines not matching are shown instead.
+pub struct Pattern {
+    matcher: Matcher,
+    is_inverted: bool,
+    text: String,
+}
+
+impl Pattern {
+    #[must_use]
+    pub fn matches(&self, line: &str) -> bool {
+        let matches = match &self.matcher {
+            Matcher::Text(text) => line.contains(text.as_str()),
+            Matcher::Regex(regex) => regex.is_match(line),
+        };
+        matches != self.is_inverted
+    }
+
+    /// Returns the indexes of the lines of `buffer` matching the pattern
+    #[must_use]
+    pub fn matching_lines(&self, buffer: &Buffer) -> Vec<usize> {
+        buffer
+            .lines
+            .iter_uncached()
+            .enumerate()
+            .filter(|(_, line)| self.matches(&line.to_string()))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (is_inverted, pattern) = match text.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, text),
+        };
+        let matcher = match pattern
+            .strip_prefix('/')
+            .and_then(|pattern| pattern.strip_suffix('/'))
+        {
+            Some(regex) => Matcher::Regex(Regex::new(regex).map_err(|error| error.to_string())?),
+            None => Matcher::Text(pattern.to_string()),
+        };
+        if pattern.is_empty() {
+            return Err("The pattern is empty".to_string());
+        }
+        Ok(Self {
+            matcher,
+            is_inverted,
+            text: text.to_string(),
+        })
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.text)
+    }
+}
+
+/// This keeps a buffer while a view shows only its lines matching a [`Pattern`].
+/// The view edits a buffer made of these lines, whose changes are copied back with [`Filter::sync`].
+pub struct Filter {
+    /// The buffer with all the lines
+    pub buffer: Buffer,
+    /// The index in [`Filter::buffer`] of each shown line
+    line_indexes: Vec<usize>,
+    /// The revision of the buffer of the shown lines when it was last copied back
+    synced_revision: u64,
+}
+
+impl Filter {
+    /// Keeps `buffer` and returns the buffer of its lines at `line_indexes`, see [`Pattern::matching_lines`], to show
+    #[must_use]
+    pub fn new(buffer: Buffer, line_indexes: Vec<usize>) -> (Self, Buffer) {
+        let shown = buffer.filtered(&line_indexes);
+        let filter = Self {
+            buffer,
+            line_indexes,
+            synced_revision: shown.revision(),
+        };
+        (filter, shown)
+    }
+
+    /// Returns the index in the full buffer of the shown line `shown_index`
+    #[must_use]
+    pub fn line_index(&self, shown_index: usize) -> Option<usize> {
+        self.line_indexes.get(shown_index).copied()
+    }
+
+    /// Returns the first shown line at or after the line `line_index` of the full buffer, or the last shown line
+    #[must_use]
+    pub fn shown_index(&self, line_index: usize) -> usize {
+        self.line_indexes
+            .partition_point(|index| *index < line_index)
+            .min(self.line_indexes.len().saturating_sub(1))
+    }
+
+    #[must_use]
+    pub fn shown_count(&self) -> usize {
+        self.line_indexes.len()
+    }
+
+    /// The width of the real line numbers drawn before the shown lines, a space included
+    #[must_use]
+    pub fn gutter_width(&self) -> usize {
+        self.line_indexes
+            .last()
+            .map_or(0, |index| index.saturating_add(1).to_string().len())
+            .saturating_add(1)
+    }
+
+    /// Copies the lines edited in `shown` since the last call back to the full buffer
+    pub fn sync(&mut self, shown: &Buffer) {
+        if shown.revision() != self.synced_revision {
+            self.buffer.copy_lines_from(shown, &self.line_indexes);
+            self.synced_revision = shown.revision();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::view::Location;
+
+    fn buffer(lines: &[&str]) -> Buffer {
+        Buffer::read(lines.join("\n").as_bytes()).unwrap()
+    }
+
+    fn to_strings(buffer: &Buffer) -> Vec<String> {
+        buffer.lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_patterns() {
+        let lines = ["INFO start", "ERROR disk", "info ERROR", "DEBUG"];
+        let shown = |pattern: &str| {
+            let pattern: Pattern = pattern.parse().unwrap();
+            lines
+                .iter()
+                .filter(|line| pattern.matches(line))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shown("ERROR"), vec!["ERROR disk", "info ERROR"]);
+        assert_eq!(shown("/^ERROR/"), vec!["ERROR disk"]);
+        assert_eq!(shown("!/(?i)info/"), vec!["ERROR disk", "DEBUG"]);
+        assert!(shown("/").is_empty());
+        assert!("/(/".parse::<Pattern>().is_err());
+        assert!("!".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn test_edit_shown_lines() {
+        let pattern: Pattern = "b".parse().unwrap();
+        let full = buffer(&["a", "b1", "c", "b2"]);
+        let line_indexes = pattern.matching_lines(&full);
+        assert!(pattern.matching_lines(&buffer(&["a"])).is_empty());
+        let (mut filter, mut shown) = Filter::new(full, line_indexes);
+        assert_eq!(to_strings(&shown), vec!["b1", "b2"]);
+        assert_eq!(filter.line_index(1), Some(3));
+        assert_eq!(filter.shown_index(2), 1);
+        assert_eq!(filter.shown_index(9), 1);
+        assert_eq!(filter.gutter_width(), 2);
+        shown.insert_char(
+            '!',
+            &Location {
+                line_index: 1,
+                grapheme_index: 2,
+            },
+        );
+        shown.insert_line(&Location::default());
+        shown.insert_text(&Location::default(), "x\ny");
+        filter.sync(&shown);
+        assert_eq!(to_strings(&filter.buffer), vec!["a", "b1", "c", "b2!"]);
+        assert!(filter.buffer.is_modified());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::editor::editorcommand::Direction;
+use crate::editor::terminal::Position;
+
+/// How many bytes each row shows
+pub const BYTES_PER_ROW: usize = 16;
+/// The bytes of a row are shown in two groups, the second one starting with this byte
+const SECOND_HALF: usize = 8;
+/// The width of the offset starting each row
+pub const OFFSET_WIDTH: usize = 8;
+/// Where the hex digits of the first byte of a row are
+const HEX_START: usize = OFFSET_WIDTH + 2;
+/// Where the character of the first byte of a row is, after its hex digits and a bar
+const TEXT_START: usize = HEX_START + BYTES_PER_ROW * 3 + 3;
+
+/// The caret of a view showing the bytes of a binary buffer, on one of the two hex digits of a byte
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexCaret {
+    pub offset: usize,
+    /// The caret is on the second digit, the one of the low four bits
+    pub is_low_digit: bool,
+}
+
+impl HexCaret {
+    #[must_use]
+    pub fn at(offset: usize) -> Self {
+        Self {
+            offset,
+            is_low_digit: false,
+        }
+    }
+
+    /// Returns the caret on the byte at `col` of the row `row`, in its hex digits or its character
+    #[must_use]
+    pub fn from_position(Position { row, col }: Position) -> Option<Self> {
+        let first_offset = row.saturating_mul(BYTES_PER_ROW);
+        if let Some(index) = col
+            .checked_sub(TEXT_START)
+            .filter(|index| *index < BYTES_PER_ROW)
+        {
+            return Some(Self::at(first_offset.saturating_add(index)));
+        }
+        (0..BYTES_PER_ROW).find_map(|index| {
+            let digit = col
+                .checked_sub(hex_column(index))
+                .filter(|digit| *digit < 2)?;
+            Some(Self {
+                offset: first_offset.saturating_add(index),
+                is_low_digit: digit == 1,
+            })
+        })
+    }
+
+    /// Where the caret is drawn, on its hex digit
+    #[must_use]
+    pub fn position(self) -> Position {
+        let (row, index) = split_offset(self.offset);
+        Position {
+            row,
+            col: hex_column(index).saturating_add(usize::from(self.is_low_digit)),
+        }
+    }
+
+    /// The screen column of the character of the byte under the caret
+    #[must_use]
+    pub fn text_column(self) -> usize {
+        TEXT_START.saturating_add(split_offset(self.offset).1)
+    }
+
+    /// Returns the caret moved by bytes, by rows or by `page_rows` rows, kept on one of the `len` bytes
+    #[must_use]
+    pub fn moved(self, direction: Direction, len: usize, page_rows: usize) -> Self {
+        let Self { offset, .. } = self;
+        let page = page_rows.saturating_mul(BYTES_PER_ROW);
+        let row_start = split_offset(offset).0.saturating_mul(BYTES_PER_ROW);
+        let offset = match direction {
+            Direction::Left | Direction::WordLeft => offset.saturating_sub(1),
+            Direction::Right | Direction::WordRight | Direction::NextWordStart => {
+                offset.saturating_add(1)
+            }
+            Direction::Up => offset.saturating_sub(BYTES_PER_ROW),
+            Direction::Down => offset.saturating_add(BYTES_PER_ROW),
+            Direction::PageUp => offset.saturating_sub(page),
+            Direction::PageDown => offset.saturating_add(page),
+            Direction::Home => row_start,
+            Direction::End => row_start.saturating_add(BYTES_PER_ROW.saturating_sub(1)),
+            Direction::FileStart => 0,
+            Direction::FileEnd => len,
+        };
+        Self::at(offset).clamped(len)
+    }
+
+    /// Returns the caret moved to the last of the `len` bytes if it is past it
+    #[must_use]
+    pub fn clamped(self, len: usize) -> Self {
+        if self.offset < len {
+            self
+        } else {
+            Self::at(len.saturating_sub(1))
+        }
+    }
+
+    /// Returns `byte` with the hex digit under the caret replaced by `digit`, which is below 16
+    #[must_use]
+    pub fn overwrite(self, byte: u8, digit: u8) -> u8 {
+        if self.is_low_digit {
+            (byte & 0xf0) | (digit & 0x0f)
+        } else {
+            (byte & 0x0f) | (digit << 4)
+        }
+    }
+
+    /// Returns the caret on the next hex digit. On the last of the `len` bytes, it stays on the low digit.
+    #[must_use]
+    pub fn next_digit(self, len: usize) -> Self {
+        if self.is_low_digit {
+            if self.offset.saturating_add(1) < len {
+                Self::at(self.offset.saturating_add(1))
+            } else {
+                self
+            }
+        } else {
+            Self {
+                is_low_digit: true,
+                ..self
+            }
+        }
+    }
+}
+
+/// Returns the row of the byte at `offset` and its index in the row
+fn split_offset(offset: usize) -> (usize, usize) {
+    (
+        offset.checked_div(BYTES_PER_ROW).unwrap_or(0),
+        offset.checked_rem(BYTES_PER_ROW).unwrap_or(0),
+    )
+}
+
+/// The screen column of the first hex digit of the byte `index` of a row, the two halves of a row being apart
+fn hex_column(index: usize) -> usize {
+    let gap = usize::from(index >= SECOND_HALF);
+    HEX_START
+        .saturating_add(index.saturating_mul(3))
+        .saturating_add(gap)
+}
+
+/// How many rows the `len` bytes take
+#[must_use]
+pub fn row_count(len: usize) -> usize {
+    len.div_ceil(BYTES_PER_ROW)
+}
+
+/// Formats the row `row` of `bytes` like `hexdump -C`: the offset of its first byte, the hex digits of its bytes
+/// and their characters, the bytes that are not printable ASCII being dots.
+/// Returns `None` past the last byte.
+#[must_use]
+pub fn format_row(bytes: &[u8], row: usize) -> Option<String> {
+    let start = row.saturating_mul(BYTES_PER_ROW);
+    let row_bytes = bytes.get(start..start.saturating_add(BYTES_PER_ROW).min(bytes.len()))?;
+    if row_bytes.is_empty() {
+        return None;
+    }
+    let mut text = format!("{start:0OFFSET_WIDTH$x}  ");
+    for (index, byte) in row_bytes.iter().enumerate() {
+        let column = hex_column(index);
+        text = format!("{text:column$}{byte:02x} ");
+    }
+    let text_start = TEXT_START.saturating_sub(1);
+    let characters: String = row_bytes
+        .iter()
+        .map(|byte| {
+            if byte.is_ascii_graphic() || *byte == b' ' {
+                char::from(*byte)
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    Some(format!("{text:text_start$}|{characters}|"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_rows() {
+        let bytes: Vec<u8> = b"\x7fELF\x02\x01\x01\0 binary\xff, then more".to_vec();
+        assert_eq!(
+            format_row(&bytes, 0).unwrap(),
+            "00000000  7f 45 4c 46 02 01 01 00  20 62 69 6e 61 72 79 ff  |.ELF.... binary.|"
+        );
+        assert_eq!(
+            format_row(&bytes, 1).unwrap(),
+            "00000010  2c 20 74 68 65 6e 20 6d  6f 72 65                 |, then more|"
+        );
+        assert_eq!(format_row(&bytes, 2), None);
+        assert_eq!(row_count(bytes.len()), 2);
+    }
+
+    #[test]
+    fn test_caret() {
+        let caret = HexCaret::at(9);
+        assert_eq!(caret.position(), Position { row: 0, col: 38 });
+        assert_eq!(caret.text_column(), 70);
+        assert_eq!(HexCaret::from_position(caret.position()), Some(caret));
+        assert_eq!(
+            HexCaret::from_position(Position { row: 1, col: 11 }),
+            Some(HexCaret {
+                offset: 16,
+                is_low_digit: true
+            })
+        );
+        assert_eq!(
+            HexCaret::from_position(Position { row: 1, col: 70 }),
+            Some(HexCaret::at(25))
+        );
+        assert_eq!(HexCaret::from_position(Position { row: 0, col: 12 }), None);
+        assert_eq!(caret.moved(Direction::Down, 20, 1), HexCaret::at(19));
+        assert_eq!(caret.moved(Direction::Home, 20, 1), HexCaret::at(0));
+        assert_eq!(caret.moved(Direction::Left, 20, 1), HexCaret::at(8));
+        assert_eq!(caret.next_digit(20).next_digit(20), HexCaret::at(10));
+        let last = HexCaret::at(19).next_digit(20);
+        assert!(last.is_low_digit);
+        assert_eq!(last.next_digit(20), last);
+        assert_eq!(caret.overwrite(0x4c, 0xa), 0xac);
+        assert_eq!(caret.next_digit(20).overwrite(0x4c, 0xa), 0x4a);
+    }
+}
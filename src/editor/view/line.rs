@@ -19,13 +19,14 @@ impl GraphemeWidth {
     }
 }
 
+#[derive(Clone)]
 struct TextFragment {
     grapheme: String,
     rendered_width: GraphemeWidth,
     replacement: Option<char>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Line {
     fragments: Vec<TextFragment>,
 }
@@ -95,6 +96,12 @@ impl Line {
         self.fragments.len()
     }
 
+    pub fn graphemes(&self) -> impl Iterator<Item = &str> {
+        self.fragments
+            .iter()
+            .map(|fragment| fragment.grapheme.as_str())
+    }
+
     pub fn width_until(&self, grapheme_index: usize) -> usize {
         self.fragments
             .iter()
@@ -106,6 +113,128 @@ impl Line {
             .sum()
     }
 
+    /// Returns the index of the grapheme drawn at column `width`, or the grapheme count if the line is shorter.
+    /// Both columns of a full-width grapheme give its index.
+    pub fn grapheme_index_at(&self, width: usize) -> usize {
+        let mut current_width: usize = 0;
+        for (index, fragment) in self.fragments.iter().enumerate() {
+            current_width = fragment.rendered_width.saturating_add(current_width);
+            if current_width > width {
+                return index;
+            }
+        }
+        self.grapheme_count()
+    }
+
+    /// Returns the index of the first grapheme drawn from column `width` or after it, or the grapheme count if there is none.
+    /// Unlike [`Line::grapheme_index_at`], a full-width grapheme started before `width` is skipped.
+    pub fn grapheme_index_from(&self, width: usize) -> usize {
+        let mut current_width: usize = 0;
+        for (index, fragment) in self.fragments.iter().enumerate() {
+            if current_width >= width {
+                return index;
+            }
+            current_width = fragment.rendered_width.saturating_add(current_width);
+        }
+        self.grapheme_count()
+    }
+
+    /// Returns the graphemes of the word, or run of spaces and punctuation, at `grapheme_index`
+    /// as defined by the Unicode word boundaries. At the end of the line, the last word is returned.
+    pub fn word_range_at(&self, grapheme_index: usize) -> Range<usize> {
+        let grapheme_index = grapheme_index.min(self.grapheme_count().saturating_sub(1));
+        let mut start = 0;
+        for end in self.word_boundaries() {
+            if end > grapheme_index {
+                return start..end;
+            }
+            start = end;
+        }
+        start..self.grapheme_count()
+    }
+
+    /// Returns the start of the word before `grapheme_index`, or 0 if there is none
+    pub fn word_start_before(&self, grapheme_index: usize) -> usize {
+        self.words()
+            .iter()
+            .rev()
+            .find(|word| word.start < grapheme_index)
+            .map_or(0, |word| word.start)
+    }
+
+    /// Returns the end of the word after `grapheme_index`, or the grapheme count if there is none
+    pub fn word_end_after(&self, grapheme_index: usize) -> usize {
+        self.words()
+            .iter()
+            .find(|word| word.end > grapheme_index)
+            .map_or(self.grapheme_count(), |word| word.end)
+    }
+
+    /// Returns the start of the first word after `grapheme_index`, if there is one
+    pub fn word_start_after(&self, grapheme_index: usize) -> Option<usize> {
+        self.words()
+            .iter()
+            .find(|word| word.start > grapheme_index)
+            .map(|word| word.start)
+    }
+
+    /// Returns the start of the first word, or 0 if the line has none
+    pub fn first_word_start(&self) -> usize {
+        self.words().first().map_or(0, |word| word.start)
+    }
+
+    /// Returns the graphemes of every word of the line, without the whitespace between them.
+    /// Consecutive punctuation makes a single word, so `::` or `+=` are skipped at once.
+    fn words(&self) -> Vec<Range<usize>> {
+        let mut words: Vec<(Range<usize>, bool)> = Vec::new();
+        let mut start = 0;
+        for end in self.word_boundaries() {
+            let segment: String = self
+                .fragments
+                .iter()
+                .skip(start)
+                .take(end.saturating_sub(start))
+                .map(|fragment| fragment.grapheme.as_str())
+                .collect();
+            let is_alphanumeric = segment
+                .chars()
+                .any(|character| character.is_alphanumeric() || character == '_');
+            if !segment.chars().all(char::is_whitespace) {
+                match words.last_mut() {
+                    Some((word, is_word_alphanumeric))
+                        if word.end == start && *is_word_alphanumeric == is_alphanumeric =>
+                    {
+                        word.end = end;
+                    }
+                    _ => words.push((start..end, is_alphanumeric)),
+                }
+            }
+            start = end;
+        }
+        words.into_iter().map(|(word, _)| word).collect()
+    }
+
+    /// Returns the grapheme index at the end of every word.
+    /// Word boundaries never split a grapheme, so every word ends at the end of a fragment.
+    fn word_boundaries(&self) -> Vec<usize> {
+        let mut fragments = self.fragments.iter();
+        let mut grapheme_index: usize = 0;
+        self.to_string()
+            .split_word_bounds()
+            .map(|word| {
+                let mut word_len: usize = 0;
+                while word_len < word.len() {
+                    let Some(fragment) = fragments.next() else {
+                        break;
+                    };
+                    word_len = word_len.saturating_add(fragment.grapheme.len());
+                    grapheme_index = grapheme_index.saturating_add(1);
+                }
+                grapheme_index
+            })
+            .collect()
+    }
+
     pub fn split_at(&mut self, grapheme_index: usize) -> Self {
         if grapheme_index > self.grapheme_count() {
             return Self::default();
@@ -189,6 +318,48 @@ mod test {
         assert_eq!(line_width, line.fragments.len());
     }
 
+    #[test]
+    fn test_grapheme_index_at() {
+        let line: Line = Line::from("aＢc");
+        assert_eq!(line.grapheme_index_at(0), 0);
+        assert_eq!(line.grapheme_index_at(1), 1);
+        assert_eq!(line.grapheme_index_at(2), 1);
+        assert_eq!(line.grapheme_index_at(3), 2);
+        assert_eq!(line.grapheme_index_at(10), 3);
+        assert_eq!(line.grapheme_index_from(1), 1);
+        assert_eq!(line.grapheme_index_from(2), 2);
+        assert_eq!(line.grapheme_index_from(3), 2);
+        assert_eq!(line.grapheme_index_from(10), 3);
+    }
+
+    #[test]
+    fn test_word_range_at() {
+        let line: Line = Line::from("let çà_va = 42;");
+        assert_eq!(line.word_range_at(0), 0..3);
+        assert_eq!(line.word_range_at(5), 4..9);
+        assert_eq!(line.word_range_at(3), 3..4);
+        assert_eq!(line.word_range_at(15), 14..15);
+    }
+
+    #[test]
+    fn test_word_movement() {
+        let line: Line = Line::from("  self::çà_va += 42;");
+        assert_eq!(line.word_end_after(0), 6);
+        assert_eq!(line.word_end_after(6), 8);
+        assert_eq!(line.word_end_after(8), 13);
+        assert_eq!(line.word_end_after(13), 16);
+        assert_eq!(line.word_end_after(18), 19);
+        assert_eq!(line.word_end_after(19), 20);
+        assert_eq!(line.word_end_after(20), 20);
+        assert_eq!(line.word_start_before(20), 19);
+        assert_eq!(line.word_start_before(19), 17);
+        assert_eq!(line.word_start_before(14), 8);
+        assert_eq!(line.word_start_before(2), 0);
+        assert_eq!(line.word_start_after(2), Some(6));
+        assert_eq!(line.word_start_after(19), None);
+        assert_eq!(line.first_word_start(), 2);
+    }
+
     #[test]
     fn test_tab() {
         let mut line: Line = Line::from("a");
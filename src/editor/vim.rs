@@ -0,0 +1,481 @@
+use crate::editor::editorcommand::Direction::{
+    Down, End, FileEnd, FileStart, Home, Left, NextWordStart, Right, Up, WordLeft, WordRight,
+};
+use crate::editor::editorcommand::{Direction, EditorCommand};
+use crate::editor::terminal::CursorShape;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::iter::repeat_n;
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+impl Mode {
+    #[must_use]
+    pub fn cursor_shape(self) -> CursorShape {
+        match self {
+            Self::Insert => CursorShape::Bar,
+            Self::Normal | Self::Visual | Self::VisualLine => CursorShape::Block,
+        }
+    }
+
+    fn is_visual(self) -> bool {
+        matches!(self, Self::Visual | Self::VisualLine)
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Normal => "NORMAL",
+            Self::Insert => "INSERT",
+            Self::Visual => "VISUAL",
+            Self::VisualLine => "VISUAL LINE",
+        };
+        write!(formatter, "{name}")
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+/// Whether an operator applies to the text between the caret and the end of a motion,
+/// or to all the lines the motion goes through
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum MotionKind {
+    Characterwise,
+    Linewise,
+}
+
+/// This is the modal editing layer: it turns keys into [`EditorCommand`]s the way vim does.
+/// In insert mode, keys are turned into commands like without the layer.
+/// In the other modes, keys are motions and operators, which can be preceded by a count.
+#[derive(Default)]
+pub struct Vim {
+    mode: Mode,
+    count: Option<usize>,
+    /// The operator waiting for its motion, with the count typed before it
+    operator: Option<(Operator, usize)>,
+    /// The first key of a command of two keys: `g` of `gg`, `q` waiting for a macro slot, or `@`
+    pending: Option<char>,
+    /// A macro is being recorded, `q` ends it
+    is_recording: bool,
+}
+
+impl Vim {
+    #[must_use]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the commands to run for `key`, which can be none while a command is being typed
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<EditorCommand> {
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('q' | 'Q'), KeyModifiers::CONTROL) => return vec![EditorCommand::Quit],
+            (KeyCode::Char('s' | 'S'), KeyModifiers::CONTROL) => return vec![EditorCommand::Save],
+            (KeyCode::Char('z' | 'Z'), KeyModifiers::CONTROL) => {
+                return vec![EditorCommand::Suspend]
+            }
+            _ => {}
+        }
+        if self.mode == Mode::Insert {
+            if key.code == KeyCode::Esc {
+                self.mode = Mode::Normal;
+                return Vec::new();
+            }
+            return EditorCommand::try_from(Event::Key(key))
+                .map_or_else(|_| Vec::new(), |command| vec![command]);
+        }
+        let character = match (key.code, key.modifiers) {
+            (KeyCode::Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) => character,
+            (KeyCode::Left, _) => 'h',
+            (KeyCode::Down, _) => 'j',
+            (KeyCode::Up, _) => 'k',
+            (KeyCode::Right, _) => 'l',
+            (KeyCode::Home, _) => '0',
+            (KeyCode::End, _) => '$',
+            (KeyCode::Esc, _) => {
+                let was_visual = self.mode.is_visual();
+                self.reset();
+                self.mode = Mode::Normal;
+                return if was_visual {
+                    vec![EditorCommand::ClearSelection]
+                } else {
+                    Vec::new()
+                };
+            }
+            // The other keys with Ctrl or Alt do what they do without the layer, like Alt-R making the view read-only
+            (_, modifiers) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.reset();
+                return EditorCommand::try_from(Event::Key(key))
+                    .map_or_else(|_| Vec::new(), |command| vec![command]);
+            }
+            _ => return Vec::new(),
+        };
+        self.handle_character(character)
+    }
+
+    fn handle_character(&mut self, character: char) -> Vec<EditorCommand> {
+        if let Some(first) = self.pending.take() {
+            return self.handle_second_key(first, character);
+        }
+        if let Some(digit) = character.to_digit(10) {
+            if digit != 0 || self.count.is_some() {
+                let digit = usize::try_from(digit).unwrap_or_default();
+                self.count = Some(
+                    self.count
+                        .unwrap_or_default()
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+                return Vec::new();
+            }
+        }
+        match character {
+            'h' => self.motion(Left, MotionKind::Characterwise),
+            'l' => self.motion(Right, MotionKind::Characterwise),
+            'j' => self.motion(Down, MotionKind::Linewise),
+            'k' => self.motion(Up, MotionKind::Linewise),
+            'w' => self.motion(NextWordStart, MotionKind::Characterwise),
+            'b' => self.motion(WordLeft, MotionKind::Characterwise),
+            'e' => self.motion(WordRight, MotionKind::Characterwise),
+            '0' => self.motion(Home, MotionKind::Characterwise),
+            '$' => self.motion(End, MotionKind::Characterwise),
+            'G' => self.motion(FileEnd, MotionKind::Linewise),
+            'g' => {
+                self.pending = Some('g');
+                Vec::new()
+            }
+            'd' => self.operator(Operator::Delete),
+            'c' => self.operator(Operator::Change),
+            'y' => self.operator(Operator::Yank),
+            _ if self.operator.is_some() => {
+                self.reset();
+                Vec::new()
+            }
+            'x' if self.mode.is_visual() => self.apply(Operator::Delete),
+            'v' => self.toggle_visual(Mode::Visual),
+            'V' => self.toggle_visual(Mode::VisualLine),
+            _ if self.mode.is_visual() => {
+                self.reset();
+                Vec::new()
+            }
+            'x' | 'X' => {
+                let direction = if character == 'x' { Right } else { Left };
+                let mut commands = vec![EditorCommand::StartSelection];
+                commands.extend(
+                    repeat_n(direction, self.take_count()).map(EditorCommand::SelectInLine),
+                );
+                commands.extend(self.apply(Operator::Delete));
+                commands
+            }
+            ':' => {
+                self.reset();
+                vec![EditorCommand::PromptGoToLine]
+            }
+            'q' if self.is_recording => {
+                self.reset();
+                self.is_recording = false;
+                vec![EditorCommand::StopRecording]
+            }
+            'q' | '@' => {
+                self.pending = Some(character);
+                Vec::new()
+            }
+            'p' => repeat_n((), self.take_count())
+                .map(|()| EditorCommand::PasteAfter)
+                .collect(),
+            'P' => repeat_n((), self.take_count())
+                .map(|()| EditorCommand::Paste)
+                .collect(),
+            'i' | 'a' | 'I' | 'A' | 'o' | 'O' => {
+                self.reset();
+                self.mode = Mode::Insert;
+                match character {
+                    'a' => vec![EditorCommand::Move(Right)],
+                    'I' => vec![EditorCommand::Move(Home)],
+                    'A' => vec![EditorCommand::Move(End)],
+                    'o' => vec![EditorCommand::OpenLineBelow],
+                    'O' => vec![EditorCommand::OpenLineAbove],
+                    _ => Vec::new(),
+                }
+            }
+            _ => {
+                self.reset();
+                Vec::new()
+            }
+        }
+    }
+
+    /// Handles the key after `first`, for the commands made of two keys
+    fn handle_second_key(&mut self, first: char, character: char) -> Vec<EditorCommand> {
+        match (first, character) {
+            ('g', 'g') => self.motion(FileStart, MotionKind::Linewise),
+            ('g', 't' | 'T') => {
+                self.reset();
+                vec![if character == 't' {
+                    EditorCommand::NextBuffer
+                } else {
+                    EditorCommand::PreviousBuffer
+                }]
+            }
+            ('q', slot) if slot.is_ascii_alphanumeric() => {
+                self.reset();
+                self.is_recording = true;
+                vec![EditorCommand::StartRecording(slot)]
+            }
+            ('@', slot) if slot == '@' || slot.is_ascii_alphanumeric() => {
+                let times = self.take_count();
+                self.reset();
+                vec![EditorCommand::PlayMacro {
+                    slot: (slot != '@').then_some(slot),
+                    times,
+                }]
+            }
+            _ => {
+                self.reset();
+                Vec::new()
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.count = None;
+        self.operator = None;
+        self.pending = None;
+    }
+
+    fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1)
+    }
+
+    fn toggle_visual(&mut self, mode: Mode) -> Vec<EditorCommand> {
+        self.reset();
+        if self.mode == mode {
+            self.mode = Mode::Normal;
+            return vec![EditorCommand::ClearSelection];
+        }
+        self.mode = mode;
+        if mode == Mode::VisualLine {
+            vec![EditorCommand::StartLineSelection]
+        } else {
+            // The grapheme under the caret is part of the selection
+            vec![EditorCommand::StartSelection, EditorCommand::Select(Right)]
+        }
+    }
+
+    /// Moves the caret, extends the visual selection, or applies the pending operator up to where the motion goes
+    fn motion(&mut self, direction: Direction, kind: MotionKind) -> Vec<EditorCommand> {
+        let count = self.take_count();
+        let Some((operator, operator_count)) = self.operator.take() else {
+            let command = if self.mode.is_visual() {
+                EditorCommand::Select
+            } else {
+                EditorCommand::Move
+            };
+            return repeat_n(direction, count).map(command).collect();
+        };
+        let count = count.saturating_mul(operator_count);
+        // Like in vim, `cw` changes the end of the word but not the whitespace after it
+        let direction = if operator == Operator::Change && direction == NextWordStart {
+            WordRight
+        } else {
+            direction
+        };
+        let mut commands = Vec::new();
+        let is_backward = matches!(direction, Up | FileStart);
+        match (kind, operator) {
+            (MotionKind::Linewise, Operator::Change) => {
+                // Changing lines keeps one line to type on
+                commands.push(EditorCommand::Move(if is_backward { End } else { Home }));
+                commands.push(EditorCommand::StartSelection);
+            }
+            (MotionKind::Linewise, _) => commands.push(EditorCommand::StartLineSelection),
+            (MotionKind::Characterwise, _) => commands.push(EditorCommand::StartSelection),
+        }
+        commands.extend(repeat_n(direction, count).map(EditorCommand::Select));
+        if (kind, operator) == (MotionKind::Linewise, Operator::Change) {
+            commands.push(EditorCommand::Select(if is_backward { Home } else { End }));
+        }
+        commands.extend(self.apply(operator));
+        commands
+    }
+
+    fn operator(&mut self, operator: Operator) -> Vec<EditorCommand> {
+        if self.mode.is_visual() {
+            return self.apply(operator);
+        }
+        let count = self.take_count();
+        match self.operator.take() {
+            None => {
+                self.operator = Some((operator, count));
+                Vec::new()
+            }
+            // `dd`, `cc` and `yy` apply to whole lines, starting with the current one
+            Some((pending, pending_count)) if pending == operator => {
+                self.operator = Some((operator, 1));
+                self.count = Some(count.saturating_mul(pending_count).saturating_sub(1));
+                self.motion(Down, MotionKind::Linewise)
+            }
+            Some(_) => {
+                self.reset();
+                Vec::new()
+            }
+        }
+    }
+
+    /// Applies `operator` to the selection and leaves the mode it was typed in
+    fn apply(&mut self, operator: Operator) -> Vec<EditorCommand> {
+        self.reset();
+        match operator {
+            Operator::Delete => {
+                self.mode = Mode::Normal;
+                vec![EditorCommand::Cut]
+            }
+            Operator::Change => {
+                self.mode = Mode::Insert;
+                vec![EditorCommand::Cut]
+            }
+            Operator::Yank => {
+                self.mode = Mode::Normal;
+                vec![EditorCommand::Copy, EditorCommand::CollapseSelection]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::editor::backend::memory::MemoryBackend;
+    use crate::editor::test::{key, row};
+    use crate::editor::Editor;
+
+    fn set_up() -> Editor<MemoryBackend> {
+        crate::editor::test::set_up(40, &["--vim", "./text-test/test.txt"])
+    }
+
+    /// Types `keys`, `\u{1b}` being Escape
+    fn type_keys(editor: &mut Editor<MemoryBackend>, keys: &str) {
+        for character in keys.chars() {
+            let code = if character == '\u{1b}' {
+                KeyCode::Esc
+            } else {
+                KeyCode::Char(character)
+            };
+            editor.evaluate_event(key(code, KeyModifiers::NONE));
+        }
+        editor.refresh_screen();
+    }
+
+    #[test]
+    fn test_operators_with_motions() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "dw");
+        assert_eq!(row(&editor, 0), "is great!");
+        type_keys(&mut editor, "2x");
+        assert_eq!(row(&editor, 0), " great!");
+        type_keys(&mut editor, "wd$");
+        assert_eq!(row(&editor, 0), "");
+        type_keys(&mut editor, "j0de");
+        assert_eq!(row(&editor, 1), " is 𝒻𝒶𝓃𝒸𝓎!");
+        type_keys(&mut editor, "$h5x");
+        assert_eq!(row(&editor, 1), " is 𝒻𝒶𝓃𝒸𝓎");
+        assert_eq!(row(&editor, 2), "rust is ѕ¢αяу!");
+        type_keys(&mut editor, "0X");
+        assert_eq!(row(&editor, 1), " is 𝒻𝒶𝓃𝒸𝓎");
+    }
+
+    #[test]
+    fn test_keys_with_modifiers() {
+        let mut editor = set_up();
+        for character in ['d', 'x'] {
+            editor.evaluate_event(key(KeyCode::Char(character), KeyModifiers::CONTROL));
+        }
+        editor.refresh_screen();
+        assert_eq!(row(&editor, 0), " is great!");
+    }
+
+    #[test]
+    fn test_view_toggles() {
+        let mut editor = set_up();
+        let alt = |editor: &mut Editor<MemoryBackend>, character| {
+            editor.evaluate_event(key(KeyCode::Char(character), KeyModifiers::ALT));
+            editor.refresh_screen();
+        };
+        alt(&mut editor, 'r');
+        type_keys(&mut editor, "x");
+        assert_eq!(row(&editor, 0), "rust is great!");
+        assert_eq!(row(&editor, 9), "The buffer is read-only");
+        type_keys(&mut editor, "v");
+        alt(&mut editor, 'r');
+        type_keys(&mut editor, "\u{1b}0x");
+        assert_eq!(row(&editor, 0), "ust is great!");
+        alt(&mut editor, 'x');
+        assert!(row(&editor, 0).starts_with("00000000  75 73 74"));
+        alt(&mut editor, 'x');
+        alt(&mut editor, 'l');
+        assert!(row(&editor, 9).starts_with("Filter lines"));
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "yyp");
+        assert_eq!(row(&editor, 0), "rust is great!");
+        assert_eq!(row(&editor, 1), "rust is great!");
+        assert_eq!(row(&editor, 2), "rust is 𝒻𝒶𝓃𝒸𝓎!");
+        type_keys(&mut editor, "2dd");
+        assert_eq!(row(&editor, 0), "rust is great!");
+        assert_eq!(row(&editor, 1), "rust is ѕ¢αяу!");
+        type_keys(&mut editor, "Gdd");
+        assert_eq!(row(&editor, 2), "~");
+        type_keys(&mut editor, "ggVjd");
+        assert_eq!(row(&editor, 0), "");
+        assert_eq!(row(&editor, 9), "-- NORMAL -- ./text-test/test.txt - 1 li");
+    }
+
+    #[test]
+    fn test_change_and_modes() {
+        let mut editor = set_up();
+        assert_eq!(editor.backend().cursor_shape(), CursorShape::Default);
+        type_keys(&mut editor, "cwhello");
+        assert_eq!(row(&editor, 0), "hello is great!");
+        assert!(row(&editor, 9).starts_with("-- INSERT --"));
+        assert_eq!(editor.backend().cursor_shape(), CursorShape::Bar);
+        type_keys(&mut editor, "\u{1b}jccbye\u{1b}");
+        assert_eq!(row(&editor, 1), "bye");
+        assert_eq!(row(&editor, 2), "rust is ѕ¢αяу!");
+        assert!(row(&editor, 9).starts_with("-- NORMAL --"));
+        assert_eq!(editor.backend().cursor_shape(), CursorShape::Block);
+        type_keys(&mut editor, "0vlly");
+        assert!(row(&editor, 9).starts_with("-- NORMAL --"));
+        type_keys(&mut editor, "P");
+        assert_eq!(row(&editor, 1), "byebye");
+    }
+
+    #[test]
+    fn test_macros() {
+        let mut editor = set_up();
+        type_keys(&mut editor, "qa");
+        assert!(row(&editor, 9).starts_with("-- NORMAL recording @a -- "));
+        type_keys(&mut editor, "I- \u{1b}jq");
+        assert_eq!(row(&editor, 0), "- rust is great!");
+        assert!(row(&editor, 9).starts_with("-- NORMAL -- "));
+        type_keys(&mut editor, "2@a");
+        assert_eq!(row(&editor, 1), "- rust is 𝒻𝒶𝓃𝒸𝓎!");
+        assert_eq!(row(&editor, 2), "- rust is ѕ¢αяу!");
+        type_keys(&mut editor, "@@");
+        assert_eq!(row(&editor, 3), "- ʎzɐɹɔ sı ʇsnɹ");
+    }
+}
@@ -8,10 +8,22 @@
     clippy::indexing_slicing
 )]
 
+use crate::editor::arguments::{Arguments, USAGE};
 use crate::editor::Editor;
 
 pub mod editor;
 
+// Printing the help and the version is what these options are for
+#[allow(clippy::print_stdout)]
 fn main() {
-    Editor::new().unwrap().run();
+    let args: Vec<String> = std::env::args().collect();
+    match Arguments::parse(&args) {
+        Ok(Arguments::Edit(options)) => Editor::new(&options).unwrap().run(),
+        Ok(Arguments::Help) => println!("{USAGE}"),
+        Ok(Arguments::Version) => println!("text-editor {}", env!("CARGO_PKG_VERSION")),
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    }
 }